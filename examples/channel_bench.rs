@@ -0,0 +1,89 @@
+use ink::thread::Channel;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ===========================================================================
+// ** channel_bench **
+// ===========================================================================
+
+// times moving items from several producers to one consumer through a
+// 'Channel' & through 'std::sync::mpsc'. run with
+// 'cargo run --release --example channel_bench'.
+
+const ITEMS: usize = 1_000_000;
+
+// ---------------------------------------------------------------------------
+
+fn bench_channel(producers: usize) -> Duration {
+    let channel = Channel::<usize>::new();
+    let start = Instant::now();
+
+    let handles: Vec<_> = (0..producers)
+        .map(|_| {
+            let channel = channel.clone();
+
+            thread::spawn(move || {
+                for i in 0..ITEMS / producers {
+                    channel.put(i).unwrap();
+                }
+            })
+        })
+        .collect();
+
+    let mut count = 0;
+
+    while channel.get().is_ok() {
+        count += 1;
+    }
+
+    handles
+        .into_iter()
+        .for_each(|handle| handle.join().unwrap());
+    assert_eq!(count, ITEMS / producers * producers);
+    start.elapsed()
+}
+
+// ---------------------------------------------------------------------------
+
+fn bench_mpsc(producers: usize) -> Duration {
+    let (sender, receiver) = mpsc::channel::<usize>();
+    let start = Instant::now();
+
+    let handles: Vec<_> = (0..producers)
+        .map(|_| {
+            let sender = sender.clone();
+
+            thread::spawn(move || {
+                for i in 0..ITEMS / producers {
+                    sender.send(i).unwrap();
+                }
+            })
+        })
+        .collect();
+
+    drop(sender);
+    let count = receiver.iter().count();
+    handles
+        .into_iter()
+        .for_each(|handle| handle.join().unwrap());
+    assert_eq!(count, ITEMS / producers * producers);
+    start.elapsed()
+}
+
+// ---------------------------------------------------------------------------
+
+fn main() {
+    for producers in [1, 4] {
+        let channel = bench_channel(producers);
+        let mpsc = bench_mpsc(producers);
+
+        println!(
+            "{} producer(s): Channel {:.1} ns/item, mpsc {:.1} ns/item, ratio {:.2}x",
+            producers,
+            channel.as_nanos() as f64 / ITEMS as f64,
+            mpsc.as_nanos() as f64 / ITEMS as f64,
+            channel.as_secs_f64() / mpsc.as_secs_f64(),
+        );
+    }
+}
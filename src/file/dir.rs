@@ -25,10 +25,8 @@ impl Directory {
 
         // store all the valid DirEntrys.
 
-        for entry in entries {
-            if let Ok(entry) = entry {
-                dir_entries.push(entry);
-            }
+        for entry in entries.flatten() {
+            dir_entries.push(entry);
         }
 
         // create the Directory struct.
@@ -45,20 +43,17 @@ impl Directory {
     // ** sanitize_path **
 
     pub fn sanitize_path(path: &str) -> String {
-        let sanitized_path = if path.ends_with("/") || path.ends_with("\\") {
-            format!("{}", path)
+        if path.ends_with("/") || path.ends_with("\\") {
+            path.to_string()
         } else {
             format!("{}/", path)
-        };
-
-        sanitized_path
+        }
     }
 
     // -----------------------------------------------------------------------
     // ** suffix **
 
     pub fn suffix(filename: &str) -> Option<&str> {
-        let extension_opt = Path::new(filename).extension().and_then(OsStr::to_str);
-        extension_opt
+        Path::new(filename).extension().and_then(OsStr::to_str)
     }
 }
@@ -19,16 +19,12 @@ impl Format {
         }
 
         let mut n_with_commas = String::with_capacity(n_length + comma_count);
-        let mut count = 1;
-
-        for c in n_string.chars().rev() {
+        for (count, c) in n_string.chars().rev().enumerate() {
             n_with_commas.push(c);
 
-            if count % 3 == 0 {
+            if (count + 1) % 3 == 0 {
                 n_with_commas.push(',');
             }
-
-            count += 1;
         }
 
         n_with_commas.chars().rev().collect()
@@ -46,17 +42,12 @@ impl Format {
         }
 
         let mut t_with_commas = String::with_capacity(t_length + comma_count);
-        let mut count = 0;
-
-        for c in t_string.chars().rev() {
-            if count % 3 == 0 && count != 0 {
-                if c != '-' {
-                    t_with_commas.push(',');
-                }
+        for (count, c) in t_string.chars().rev().enumerate() {
+            if count % 3 == 0 && count != 0 && c != '-' {
+                t_with_commas.push(',');
             }
 
             t_with_commas.push(c);
-            count += 1;
         }
 
         t_with_commas.chars().rev().collect()
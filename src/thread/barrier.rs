@@ -0,0 +1,116 @@
+use std::sync::{Arc, Condvar, Mutex};
+
+// ===========================================================================
+// ** Barrier **
+// ===========================================================================
+
+// a rendezvous for a fixed number of threads. each 'wait' blocks until all of
+// them have arrived, then they proceed together & the barrier resets for the
+// next round. the generation count keeps a thread that races ahead into the
+// next round from releasing the threads still leaving the last one.
+
+struct BarrierState {
+    arrived: usize,
+    generation: u64,
+}
+
+pub struct Barrier {
+    count: usize,
+    state: Mutex<BarrierState>,
+    condvar: Condvar,
+}
+
+impl Barrier {
+    // -----------------------------------------------------------------------
+
+    pub fn new(count: usize) -> Self {
+        assert!(count > 0, "count must be at least 1");
+
+        Barrier {
+            count,
+            state: Mutex::new(BarrierState {
+                arrived: 0,
+                generation: 0,
+            }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn arc(count: usize) -> Arc<Self> {
+        Arc::new(Barrier::new(count))
+    }
+
+    // -----------------------------------------------------------------------
+    // block until 'count' threads are waiting. returns 'true' on the last
+    // thread to arrive, so one of them can do the per-round work.
+
+    pub fn wait(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.arrived += 1;
+
+        if state.arrived == self.count {
+            state.arrived = 0;
+            state.generation += 1;
+            self.condvar.notify_all();
+            return true;
+        }
+
+        let generation = state.generation;
+        let _state = self
+            .condvar
+            .wait_while(state, |state| state.generation == generation)
+            .unwrap();
+
+        false
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the number of completed rounds
+
+    pub fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::AtomicInteger;
+    use std::thread;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_barrier() {
+        let barrier = Barrier::new(4);
+        let arrived = AtomicInteger::new(0);
+        let leaders = AtomicInteger::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for round in 1..=3 {
+                        arrived.increment();
+
+                        if barrier.wait() {
+                            leaders.increment();
+                        }
+
+                        // nobody leaves a round before everyone has arrived
+
+                        assert!(arrived.get() >= round * 4);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(leaders.get(), 3);
+        assert_eq!(barrier.generation(), 3);
+    }
+}
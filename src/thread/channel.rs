@@ -1,31 +1,546 @@
-use std::collections::VecDeque;
-use std::sync::{Arc, Condvar, Mutex};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering, fence};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use crate::thread::AtomicInteger;
+use crate::thread::Event;
+use crate::thread::Select;
+use crate::thread::StopToken;
+
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+
+// how many times a consumer of an inbox channel yields to the producers
+// before blocking on the condvar
+
+const YIELD_LIMIT: u32 = 16;
+
+// ===========================================================================
+// ** ChannelWait **
+// ===========================================================================
+
+pub(crate) trait ChannelWait {
+    fn add_event(&self, event: Event<usize>, listener_id: usize);
+    fn remove_event(&self, listener_id: usize);
+    fn is_ready(&self) -> bool;
+}
+
+// ===========================================================================
+// ** SendError / RecvError **
+// ===========================================================================
+
+// returned by 'put' once the channel is closed. holds the rejected item.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+// why 'get' returned without an item. 'Closed' is only reported once the
+// queued items have been taken. 'AllSendersDropped' means this is the last
+// handle left, & 'WouldDeadlock' means every other handle is blocked in 'get'
+// too, so none of them could ever put an item.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    Closed,
+    AllSendersDropped,
+    WouldDeadlock,
+    Timeout,
+}
+
+// ===========================================================================
+// ** ProducerId **
+// ===========================================================================
+
+// identifies the handle that put an item. each clone of a channel gets its
+// own id.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProducerId(pub i32);
+
+// ===========================================================================
+// ** OverflowPolicy **
+// ===========================================================================
+
+// what 'put' does when a bounded channel is full. dropped items are counted
+// in the channel's stats.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    Block,
+    DropNewest,
+    DropOldest,
+}
+
+// ===========================================================================
+// ** ChannelStats **
+// ===========================================================================
+
+// a snapshot of a channel's counters, taken after collecting the items put
+// into the shards or the inbox. 'high_water' is the most items the channel
+// has held at once, counting those items from when they were collected.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelStats {
+    pub name: String,
+    pub put_count: usize,
+    pub get_count: usize,
+    pub depth: usize,
+    pub high_water: usize,
+    pub dropped_count: usize,
+}
 
 // ===========================================================================
 
+// the state guarded by the channel's mutex. each item is queued with the id
+// of the handle that put it. 'release_epoch' is bumped each
+// time the waiting consumers are released because no other handle could
+// ever put an item, so a waiter can tell a release from a spurious wakeup.
+// 'release_reason' is the error the released waiters return.
+
+struct ChannelState<T> {
+    items: VecDeque<(ProducerId, T)>,
+    put_count: usize,
+    get_count: usize,
+    high_water: usize,
+    dropped_count: usize,
+    idle_timeout: Option<Duration>,
+    last_put: Instant,
+    release_epoch: u64,
+    release_reason: RecvError,
+    events: HashMap<usize, Event<usize>>,
+    inbox: Option<mpsc::Receiver<(ProducerId, T)>>,
+    on_producers_dropped: Vec<Box<dyn FnOnce() + Send>>,
+    #[cfg(feature = "async")]
+    wakers: Vec<Waker>,
+}
+
+impl<T> ChannelState<T> {
+    // -----------------------------------------------------------------------
+
+    fn push(&mut self, producer: ProducerId, item: T) {
+        self.items.push_back((producer, item));
+        self.put_count += 1;
+        self.high_water = self.high_water.max(self.items.len());
+
+        if self.idle_timeout.is_some() {
+            self.last_put = Instant::now();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn pop(&mut self) -> Option<(ProducerId, T)> {
+        let item = self.items.pop_front();
+        self.get_count += item.is_some() as usize;
+        item
+    }
+
+    // -----------------------------------------------------------------------
+    // remove up to 'max' items from the front of the queue
+
+    fn take(&mut self, max: usize) -> Vec<T> {
+        let count = max.min(self.items.len());
+        self.get_count += count;
+        self.items.drain(..count).map(|(_, item)| item).collect()
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the number of select events & async wakers waiting for an item
+
+    fn listener_count(&self) -> usize {
+        #[cfg(feature = "async")]
+        return self.events.len() + self.wakers.len();
+
+        #[cfg(not(feature = "async"))]
+        return self.events.len();
+    }
+}
+
+// the items put into a sharded channel collect in the shard picked by the
+// producing thread, & are moved onto the main queue whenever a consumer locks
+// the channel. an unbounded channel that isn't sharded does the same through
+// 'inbox', a lock-free std 'Sender' whose 'Receiver' sits in the state. once
+// an idle timeout is set, an inbox put stamps 'inbox_put', in nanoseconds
+// since 'created', so the timeout sees the put before the item is collected.
+// 'listeners' counts the select events & async wakers so a producer only
+// takes the main lock when someone needs waking.
+
 struct ChannelData<T> {
-    mutex: Mutex<VecDeque<T>>,
+    mutex: Mutex<ChannelState<T>>,
     put_event: Condvar,
-    end_count: AtomicInteger,
+    get_event: Condvar,
+    capacity: Option<usize>,
+    overflow: OverflowPolicy,
+    shards: Vec<Mutex<VecDeque<(ProducerId, T)>>>,
+    inbox: Option<mpsc::Sender<(ProducerId, T)>>,
+    inbox_put: AtomicU64,
+    idle_tracked: AtomicBool,
+    created: Instant,
+    closed: AtomicBool,
+    listeners: AtomicInteger,
     open_count: AtomicInteger,
     wait_count: AtomicInteger,
+    put_wait_count: AtomicInteger,
     instance_counter: AtomicInteger,
-    _name: String,
+    name: String,
 }
 
 impl<T> ChannelData<T> {
-    fn new(name: &str) -> Self {
+    fn new(
+        name: &str,
+        capacity: Option<usize>,
+        overflow: OverflowPolicy,
+        shard_count: usize,
+    ) -> Self {
+        let (inbox, outbox) = match (capacity, shard_count) {
+            (None, 0) => {
+                let (sender, receiver) = mpsc::channel();
+                (Some(sender), Some(receiver))
+            }
+            _ => (None, None),
+        };
+
         ChannelData {
-            mutex: Mutex::new(VecDeque::new()),
+            mutex: Mutex::new(ChannelState {
+                items: VecDeque::new(),
+                put_count: 0,
+                get_count: 0,
+                high_water: 0,
+                dropped_count: 0,
+                idle_timeout: None,
+                last_put: Instant::now(),
+                release_epoch: 0,
+                release_reason: RecvError::AllSendersDropped,
+                events: HashMap::new(),
+                inbox: outbox,
+                on_producers_dropped: Vec::new(),
+                #[cfg(feature = "async")]
+                wakers: Vec::new(),
+            }),
             put_event: Condvar::new(),
-            end_count: AtomicInteger::new(0),
+            get_event: Condvar::new(),
+            capacity,
+            overflow,
+            shards: (0..shard_count)
+                .map(|_| Mutex::new(VecDeque::new()))
+                .collect(),
+            inbox,
+            inbox_put: AtomicU64::new(0),
+            idle_tracked: AtomicBool::new(false),
+            created: Instant::now(),
+            closed: AtomicBool::new(false),
+            listeners: AtomicInteger::new(0),
             open_count: AtomicInteger::new(0),
             wait_count: AtomicInteger::new(0),
+            put_wait_count: AtomicInteger::new(0),
             instance_counter: AtomicInteger::new(0),
-            _name: name.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // a rendezvous channel has room for one item per consumer waiting in 'get'
+
+    fn is_full(&self, state: &ChannelState<T>) -> bool {
+        match self.capacity {
+            Some(0) => state.items.len() >= self.wait_count.get() as usize,
+            Some(capacity) => state.items.len() >= capacity,
+            None => false,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // returns 'true' if a handle that isn't blocked in 'get' would be the last
+    // one able to put an item, so its own 'get' would fail straight away
+
+    fn is_disconnected(&self) -> bool {
+        self.wait_count.get() + 1 >= self.open_count.get()
+    }
+
+    // -----------------------------------------------------------------------
+    // returns why a disconnected channel can't deliver an item
+
+    fn disconnect_reason(&self) -> RecvError {
+        if self.open_count.get() > 1 {
+            RecvError::WouldDeadlock
+        } else {
+            RecvError::AllSendersDropped
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // release the consumers waiting in 'get' because no handle is left that
+    // could put an item. returns the error they report.
+
+    fn release(&self, state: &mut ChannelState<T>) -> RecvError {
+        state.release_epoch += 1;
+        state.release_reason = self.disconnect_reason();
+        self.put_event.notify_all();
+        state.release_reason
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    // -----------------------------------------------------------------------
+    // lock the channel, collect any items waiting in the shards or the inbox
+    // & close the channel if it has been idle too long
+
+    fn lock(&self) -> MutexGuard<'_, ChannelState<T>> {
+        let mut state = self.mutex.lock().unwrap();
+        self.collect_pending(&mut state);
+        self.check_idle(&mut state);
+        state
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'lock', but only collects the pending items once the main queue
+    // runs dry. they were put after everything already queued, so a consumer
+    // taking from the front doesn't need them yet.
+
+    fn lock_front(&self) -> MutexGuard<'_, ChannelState<T>> {
+        let mut state = self.mutex.lock().unwrap();
+
+        if state.items.is_empty() {
+            self.collect_pending(&mut state);
+        }
+
+        self.check_idle(&mut state);
+        state
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn close(&self, state: &mut ChannelState<T>) {
+        if self.is_closed() {
+            return;
+        }
+
+        self.closed.store(true, Ordering::Release);
+        self.trigger_events(state);
+        self.put_event.notify_all();
+        self.get_event.notify_all();
+    }
+
+    // -----------------------------------------------------------------------
+    // returns when the channel closes itself if nothing is put before then
+
+    fn idle_deadline(&self, state: &ChannelState<T>) -> Option<Instant> {
+        let inbox_put = self.created + Duration::from_nanos(self.inbox_put.load(Ordering::Acquire));
+
+        state
+            .idle_timeout
+            .map(|timeout| state.last_put.max(inbox_put) + timeout)
+    }
+
+    // -----------------------------------------------------------------------
+    // record the time of an inbox put for the idle timeout
+
+    fn stamp_inbox_put(&self) {
+        if !self.idle_tracked.load(Ordering::Acquire) {
+            return;
+        }
+
+        let nanos = self.created.elapsed().as_nanos() as u64;
+        self.inbox_put.fetch_max(nanos, Ordering::AcqRel);
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn check_idle(&self, state: &mut ChannelState<T>) {
+        if self
+            .idle_deadline(state)
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.close(state);
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // move the items put without the channel lock onto the main queue. the
+    // fence pairs with the one in 'wake_consumers': either the consumer sees
+    // the item, or the producer sees the consumer waiting.
+
+    fn collect_pending(&self, state: &mut ChannelState<T>) {
+        for shard in self.shards.iter() {
+            for (producer, item) in shard.lock().unwrap().drain(..) {
+                state.push(producer, item);
+            }
+        }
+
+        if state.inbox.is_some() {
+            fence(Ordering::SeqCst);
+
+            while let Some(Ok((producer, item))) =
+                state.inbox.as_ref().map(|inbox| inbox.try_recv())
+            {
+                state.push(producer, item);
+            }
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the calling thread's shard, or 'None' if the channel isn't sharded
+
+    fn shard(&self) -> Option<&Mutex<VecDeque<(ProducerId, T)>>> {
+        if self.shards.is_empty() {
+            return None;
         }
+
+        let mut hasher = DefaultHasher::new();
+        thread::current().id().hash(&mut hasher);
+        let index = hasher.finish() as usize % self.shards.len();
+        Some(&self.shards[index])
+    }
+
+    // -----------------------------------------------------------------------
+    // after a sharded or inbox put, lock the channel only if someone needs
+    // waking
+
+    fn wake_consumers(&self) {
+        fence(Ordering::SeqCst);
+
+        if self.wait_count.get() > 0 || self.listeners.get() > 0 {
+            let mut state = self.lock();
+            self.trigger_events(&mut state);
+            self.notify_consumers();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // the notify helpers skip the condvar when nobody is blocked on it, which
+    // saves a futex wake per item on the uncontended path. the wait counts
+    // only change under the channel lock, so callers must hold it.
+
+    fn notify_consumer(&self) {
+        if self.wait_count.get() > 0 {
+            self.put_event.notify_one();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn notify_consumers(&self) {
+        if self.wait_count.get() > 0 {
+            self.put_event.notify_all();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn notify_producer(&self) {
+        if self.put_wait_count.get() > 0 {
+            self.get_event.notify_one();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn notify_producers(&self) {
+        if self.put_wait_count.get() > 0 {
+            self.get_event.notify_all();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // wait until there's room for another item, or make room by following the
+    // overflow policy. returns 'false' if the new item should be dropped, or
+    // if 'deadline' passed while blocking.
+
+    fn make_room<'a>(
+        &self,
+        mut state: MutexGuard<'a, ChannelState<T>>,
+        deadline: Option<Instant>,
+    ) -> (MutexGuard<'a, ChannelState<T>>, bool) {
+        while self.is_full(&state) && !self.is_closed() {
+            match self.overflow {
+                OverflowPolicy::Block => {
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        return (state, false);
+                    }
+
+                    self.trigger_events(&mut state);
+                    self.notify_consumers();
+                    state = self.wait_for_space(state, deadline);
+                }
+                OverflowPolicy::DropNewest => {
+                    state.dropped_count += 1;
+                    return (state, false);
+                }
+                OverflowPolicy::DropOldest => {
+                    state.items.pop_front();
+                    state.dropped_count += 1;
+                }
+            }
+        }
+
+        (state, true)
+    }
+
+    // -----------------------------------------------------------------------
+    // block on 'get_event' until a consumer makes room or 'deadline' passes
+
+    fn wait_for_space<'a>(
+        &self,
+        state: MutexGuard<'a, ChannelState<T>>,
+        deadline: Option<Instant>,
+    ) -> MutexGuard<'a, ChannelState<T>> {
+        self.put_wait_count.increment();
+
+        let state = match deadline {
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                self.get_event.wait_timeout(state, timeout).unwrap().0
+            }
+            None => self.get_event.wait(state).unwrap(),
+        };
+
+        self.put_wait_count.decrement();
+        state
+    }
+
+    // -----------------------------------------------------------------------
+    // trigger the events of any listeners waiting for the channel
+
+    fn trigger_events(&self, state: &mut ChannelState<T>) {
+        for (_, event) in state.events.drain() {
+            event.trigger();
+        }
+
+        #[cfg(feature = "async")]
+        self.wake_receivers(state);
+
+        self.update_listeners(state);
+    }
+
+    // -----------------------------------------------------------------------
+    // wake the tasks polling a 'recv_async' future
+
+    #[cfg(feature = "async")]
+    fn wake_receivers(&self, state: &mut ChannelState<T>) {
+        for waker in state.wakers.drain(..) {
+            waker.wake();
+        }
+
+        self.update_listeners(state);
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn update_listeners(&self, state: &ChannelState<T>) {
+        self.listeners.set(state.listener_count() as i32);
     }
 }
 
@@ -46,8 +561,54 @@ impl<T> Channel<T> {
     // -----------------------------------------------------------------------
 
     pub fn named(name: &str) -> Self {
+        Channel::create(name, None, OverflowPolicy::Block, 0)
+    }
+
+    // -----------------------------------------------------------------------
+    // a channel holding at most 'capacity' items. 'put' blocks while it's full.
+
+    pub fn bounded(capacity: usize) -> Self {
+        Channel::bounded_with(capacity, OverflowPolicy::Block)
+    }
+
+    // -----------------------------------------------------------------------
+    // a channel holding at most 'capacity' items, where 'overflow' decides
+    // what 'put' does while it's full
+
+    pub fn bounded_with(capacity: usize, overflow: OverflowPolicy) -> Self {
+        assert!(capacity > 0, "capacity must be at least 1");
+        Channel::create("", Some(capacity), overflow, 0)
+    }
+
+    // -----------------------------------------------------------------------
+    // a zero-capacity channel. 'put' blocks until a consumer is waiting in
+    // 'get' & hands the item straight to it.
+
+    pub fn rendezvous() -> Self {
+        Channel::create("", Some(0), OverflowPolicy::Block, 0)
+    }
+
+    // -----------------------------------------------------------------------
+    // an unbounded channel that spreads its producers over 'shard_count'
+    // queues to cut lock contention. each producer's items stay in order, but
+    // items from different producers may interleave differently than they
+    // were put.
+
+    pub fn sharded(shard_count: usize) -> Self {
+        assert!(shard_count > 0, "shard count must be at least 1");
+        Channel::create("", None, OverflowPolicy::Block, shard_count)
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn create(
+        name: &str,
+        capacity: Option<usize>,
+        overflow: OverflowPolicy,
+        shard_count: usize,
+    ) -> Self {
         let mut channel = Channel {
-            data: Arc::new(ChannelData::new(name)),
+            data: Arc::new(ChannelData::new(name, capacity, overflow, shard_count)),
             instance_id: 0,
         };
 
@@ -57,78 +618,1390 @@ impl<T> Channel<T> {
     }
 
     // -----------------------------------------------------------------------
+    // returns the name given to 'named', or an empty string
 
-    pub fn end(&self) {
-        let deque = self.data.mutex.lock().unwrap();
-        self.data.end_count.increment();
+    pub fn name(&self) -> &str {
+        &self.data.name
+    }
 
-        if deque.is_empty() {
-            self.data.put_event.notify_all();
-        }
+    // -----------------------------------------------------------------------
+    // returns 'None' for an unbounded channel & 'Some(0)' for a rendezvous one
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.data.capacity
     }
 
     // -----------------------------------------------------------------------
+    // returns the id that tags the items put through this handle
 
-    pub fn get(&self) -> Option<T> {
-        let mut deque = self.data.mutex.lock().unwrap();
+    pub fn producer_id(&self) -> ProducerId {
+        ProducerId(self.instance_id)
+    }
 
-        if deque.len() > 0 {
-            return deque.pop_front();
-        }
+    // -----------------------------------------------------------------------
+    // returns the number of queued items
 
-        let end = self.data.end_count.get() > 0;
+    pub fn len(&self) -> usize {
+        self.data.lock().items.len()
+    }
 
-        if end {
-            self.data.put_event.notify_all();
-            return None;
-        }
+    // -----------------------------------------------------------------------
 
-        let wait_count = self.data.wait_count.get();
-        let open_count = self.data.open_count.get();
+    pub fn is_empty(&self) -> bool {
+        self.data.lock().items.is_empty()
+    }
 
-        if wait_count + 1 == open_count {
-            self.data.put_event.notify_all();
-            return None;
-        }
+    // -----------------------------------------------------------------------
+    // returns the channel's counters, shared by this handle & its clones
 
-        self.data.wait_count.increment();
-        let mut deque = self.data.put_event.wait(deque).unwrap();
-        self.data.wait_count.decrement();
-        deque.pop_front()
+    pub fn stats(&self) -> ChannelStats {
+        let state = self.data.lock();
+
+        ChannelStats {
+            name: self.data.name.clone(),
+            put_count: state.put_count,
+            get_count: state.get_count,
+            depth: state.items.len(),
+            high_water: state.high_water,
+            dropped_count: state.dropped_count,
+        }
     }
 
     // -----------------------------------------------------------------------
+    // returns 'true' once 'end' or 'close' has been called on any handle
 
-    pub fn put(&self, item: T) {
-        let mut deque = self.data.mutex.lock().unwrap();
-        deque.push_back(item);
-        self.data.put_event.notify_one();
+    pub fn is_ended(&self) -> bool {
+        self.data.is_closed()
     }
-}
 
-impl<T> Clone for Channel<T> {
     // -----------------------------------------------------------------------
+    // returns the number of live handles (this one & its clones)
 
-    fn clone(&self) -> Self {
-        let channel = Channel {
-            data: self.data.clone(),
-            instance_id: self.data.instance_counter.increment(),
-        };
+    pub fn open_handles(&self) -> usize {
+        self.data.open_count.get() as usize
+    }
 
-        channel.data.open_count.increment();
-        channel
+    // -----------------------------------------------------------------------
+    // end the channel & reject any further 'put'. consumers still receive the
+    // items already queued before 'get' reports 'RecvError::Closed'.
+
+    pub fn close(&self) {
+        let mut state = self.data.lock();
+        self.data.close(&mut state);
     }
-}
 
-impl<T> Drop for Channel<T> {
     // -----------------------------------------------------------------------
+    // run 'callback' once every other handle has been dropped, leaving only
+    // one. it runs on the thread that drops the last of them, or straight
+    // away if there are no other handles.
 
-    fn drop(&mut self) {
-        let open = self.data.open_count.decrement() - 1;
-        let waiting = self.data.wait_count.get();
+    pub fn on_all_producers_dropped(&self, callback: impl FnOnce() + Send + 'static) {
+        let mut state = self.data.lock();
 
-        if waiting == open {
-            self.data.put_event.notify_all();
+        if self.data.open_count.get() > 1 {
+            state.on_producers_dropped.push(Box::new(callback));
+            return;
         }
+
+        drop(state);
+        callback();
+    }
+
+    // -----------------------------------------------------------------------
+    // close the channel when 'token' is stopped, the same as calling 'close'
+
+    pub fn stop_on(&self, token: &StopToken)
+    where
+        T: Send + 'static,
+    {
+        token.on_stop_weak(&self.data, |data| {
+            let mut state = data.lock();
+            data.close(&mut state);
+        });
+    }
+
+    // -----------------------------------------------------------------------
+    // close the channel once nothing has been put for 'timeout'. the timeout
+    // is checked whenever the channel is used, & by consumers waiting in 'get'.
+
+    pub fn set_idle_timeout(&self, timeout: Duration) {
+        let mut state = self.data.lock();
+        state.idle_timeout = Some(timeout);
+        state.last_put = Instant::now();
+        self.data.idle_tracked.store(true, Ordering::Release);
+        self.data.put_event.notify_all();
+    }
+
+    // -----------------------------------------------------------------------
+    // the same as 'close'. once a channel has ended nobody will 'get' what's
+    // put after it, so 'put' fails instead of queueing the item.
+
+    pub fn end(&self) {
+        self.close();
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn get(&self) -> Result<T, RecvError> {
+        self.get_until(None).map(|(_, item)| item)
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'get', but also returns the id of the handle that put the item
+
+    pub fn get_tagged(&self) -> Result<(ProducerId, T), RecvError> {
+        self.get_until(None)
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'get', but gives up with 'RecvError::Timeout' once 'timeout' has
+    // passed
+
+    pub fn get_timeout(&self, timeout: Duration) -> Result<T, RecvError> {
+        self.get_deadline(Instant::now() + timeout)
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'get_timeout', but against a fixed point in time so several waits
+    // can share one deadline
+
+    pub fn get_deadline(&self, deadline: Instant) -> Result<T, RecvError> {
+        self.get_until(Some(deadline)).map(|(_, item)| item)
+    }
+
+    // -----------------------------------------------------------------------
+    // wait for an item until 'deadline'
+
+    fn get_until(&self, deadline: Option<Instant>) -> Result<(ProducerId, T), RecvError> {
+        let mut state = self.wait_for_items(deadline)?;
+        let item = state.pop().unwrap();
+        self.data.notify_producer();
+        Ok(item)
+    }
+
+    // -----------------------------------------------------------------------
+    // take up to 'max' items under a single lock, blocking only while the
+    // channel is empty. returns an empty vector when 'get' would fail.
+
+    pub fn get_many(&self, max: usize) -> Vec<T> {
+        if max == 0 {
+            return Vec::new();
+        }
+
+        let Ok(mut state) = self.wait_for_items(None) else {
+            return Vec::new();
+        };
+
+        self.data.notify_producers();
+        state.take(max)
+    }
+
+    // -----------------------------------------------------------------------
+    // lock the channel & wait until it has items. the returned state always
+    // has at least one item queued.
+
+    fn wait_for_items(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<MutexGuard<'_, ChannelState<T>>, RecvError> {
+        let mut state = self.data.lock_front();
+        let mut released = false;
+        let mut spins = 0;
+
+        loop {
+            if !state.items.is_empty() {
+                return Ok(state);
+            }
+
+            self.data.check_idle(&mut state);
+
+            if self.data.is_closed() {
+                self.data.put_event.notify_all();
+                return Err(RecvError::Closed);
+            }
+
+            if released {
+                return Err(state.release_reason);
+            }
+
+            let wait_count = self.data.wait_count.get();
+            let open_count = self.data.open_count.get();
+
+            if wait_count + 1 == open_count {
+                return Err(self.data.release(&mut state));
+            }
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(RecvError::Timeout);
+            }
+
+            let wake_at = match (deadline, self.data.idle_deadline(&state)) {
+                (Some(deadline), Some(idle)) => Some(deadline.min(idle)),
+                (deadline, idle) => deadline.or(idle),
+            };
+
+            // an inbox put doesn't lock the channel, so give the producers a
+            // few chances to run before paying for a condvar wait
+
+            if self.data.inbox.is_some() && spins < YIELD_LIMIT {
+                spins += 1;
+                drop(state);
+                thread::yield_now();
+                state = self.data.lock_front();
+                continue;
+            }
+
+            let release_epoch = state.release_epoch;
+            self.data.wait_count.increment();
+
+            // a sharded or inbox put may have missed the wait count & landed
+            // after the pending items were collected
+
+            self.data.collect_pending(&mut state);
+
+            if !state.items.is_empty() {
+                self.data.wait_count.decrement();
+                continue;
+            }
+
+            if self.data.capacity == Some(0) {
+                self.data.notify_producers();
+            }
+
+            state = match wake_at {
+                Some(wake_at) => {
+                    let timeout = wake_at.saturating_duration_since(Instant::now());
+                    self.data.put_event.wait_timeout(state, timeout).unwrap().0
+                }
+                None => self.data.put_event.wait(state).unwrap(),
+            };
+
+            self.data.wait_count.decrement();
+            released = state.release_epoch != release_epoch;
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a future that resolves like 'get', without blocking the thread
+
+    #[cfg(feature = "async")]
+    pub fn recv_async(&self) -> RecvFuture<'_, T> {
+        RecvFuture { channel: self }
+    }
+
+    // -----------------------------------------------------------------------
+    // remove & return every queued item in one step. doesn't block.
+
+    pub fn drain(&self) -> Vec<T> {
+        let mut state = self.data.lock();
+        self.data.notify_producers();
+        state.take(usize::MAX)
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the item back if the channel has been closed
+
+    pub fn put(&self, item: T) -> Result<(), SendError<T>> {
+        self.put_until(item, None).map_err(SendError)
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'put', but a full bounded channel gives up & returns the item once
+    // 'timeout' has passed
+
+    pub fn put_timeout(&self, item: T, timeout: Duration) -> Result<(), T> {
+        self.put_until(item, Some(Instant::now() + timeout))
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the item back if the channel is closed, or if it's still full
+    // at 'deadline'
+
+    fn put_until(&self, item: T, deadline: Option<Instant>) -> Result<(), T> {
+        if let Some(shard) = self.data.shard() {
+            {
+                let mut shard = shard.lock().unwrap();
+
+                if self.data.is_closed() {
+                    return Err(item);
+                }
+
+                shard.push_back((self.producer_id(), item));
+            }
+
+            self.data.wake_consumers();
+            return Ok(());
+        }
+
+        if let Some(inbox) = &self.data.inbox {
+            if self.data.is_closed() {
+                return Err(item);
+            }
+
+            inbox.send((self.producer_id(), item)).unwrap();
+            self.data.stamp_inbox_put();
+            self.data.wake_consumers();
+            return Ok(());
+        }
+
+        let (mut state, accepted) = self.data.make_room(self.data.lock(), deadline);
+
+        if self.data.is_closed() {
+            return Err(item);
+        }
+
+        if !accepted {
+            return match self.data.overflow {
+                OverflowPolicy::Block => Err(item),
+                _ => Ok(()),
+            };
+        }
+
+        state.push(self.producer_id(), item);
+        self.data.trigger_events(&mut state);
+        self.data.notify_consumer();
+        Ok(())
+    }
+
+    // -----------------------------------------------------------------------
+    // put every item from 'items' under a single lock & wake the consumers
+    // once. a full bounded channel applies its overflow policy to each item,
+    // waking the consumers early if it has to wait for space. if the channel
+    // is closed part way, the items that weren't put are returned in the
+    // error.
+
+    pub fn put_many(&self, items: impl IntoIterator<Item = T>) -> Result<(), SendError<Vec<T>>> {
+        let mut items = items.into_iter();
+
+        if let Some(shard) = self.data.shard() {
+            {
+                let mut shard = shard.lock().unwrap();
+
+                if self.data.is_closed() {
+                    return Err(SendError(items.collect()));
+                }
+
+                shard.extend(items.map(|item| (self.producer_id(), item)));
+            }
+
+            self.data.wake_consumers();
+            return Ok(());
+        }
+
+        if let Some(inbox) = &self.data.inbox {
+            if self.data.is_closed() {
+                return Err(SendError(items.collect()));
+            }
+
+            for item in items {
+                inbox.send((self.producer_id(), item)).unwrap();
+            }
+
+            self.data.stamp_inbox_put();
+            self.data.wake_consumers();
+            return Ok(());
+        }
+
+        let mut state = self.data.lock();
+        let mut result = Ok(());
+
+        while let Some(item) = items.next() {
+            let accepted;
+            (state, accepted) = self.data.make_room(state, None);
+
+            if self.data.is_closed() {
+                result = Err(SendError(std::iter::once(item).chain(items).collect()));
+                break;
+            }
+
+            if accepted {
+                state.push(self.producer_id(), item);
+            }
+        }
+
+        if !state.items.is_empty() {
+            self.data.trigger_events(&mut state);
+            self.data.notify_consumers();
+        }
+
+        result
+    }
+
+    // -----------------------------------------------------------------------
+    // put 'item' without blocking. returns the item if the channel is full or
+    // closed.
+
+    pub fn try_put(&self, item: T) -> Result<(), T> {
+        if !self.data.shards.is_empty() || self.data.inbox.is_some() {
+            return self.put(item).map_err(|error| error.0);
+        }
+
+        let mut state = self.data.lock();
+
+        if self.data.is_full(&state) || self.data.is_closed() {
+            return Err(item);
+        }
+
+        state.push(self.producer_id(), item);
+        self.data.trigger_events(&mut state);
+        self.data.notify_consumer();
+        Ok(())
+    }
+}
+
+impl<T: Clone> Channel<T> {
+    // -----------------------------------------------------------------------
+    // returns a clone of the next item without removing it. doesn't block.
+
+    pub fn peek(&self) -> Option<T> {
+        let state = self.data.lock();
+        state.items.front().map(|(_, item)| item.clone())
+    }
+}
+
+impl<T: fmt::Debug> Channel<T> {
+    // -----------------------------------------------------------------------
+    // returns the first 'limit' queued items formatted with 'Debug', for
+    // looking inside a stuck channel. doesn't remove anything.
+
+    pub fn snapshot_debug(&self, limit: usize) -> Vec<String> {
+        let state = self.data.lock();
+        state
+            .items
+            .iter()
+            .take(limit)
+            .map(|(_, item)| format!("{item:?}"))
+            .collect()
+    }
+}
+
+impl<T: Send + 'static> Channel<T> {
+    // -----------------------------------------------------------------------
+    // returns a channel that receives 'func' applied to each item of this
+    // one. the items are transformed on a new thread, which closes the
+    // returned channel once this one can't 'get' any more.
+
+    pub fn map<U: Send + 'static>(
+        self,
+        mut func: impl FnMut(T) -> U + Send + 'static,
+    ) -> Channel<U> {
+        self.forward(move |item| Some(func(item)))
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'map', but passes on only the items that 'predicate' accepts
+
+    pub fn filter(self, mut predicate: impl FnMut(&T) -> bool + Send + 'static) -> Channel<T> {
+        self.forward(move |item| predicate(&item).then_some(item))
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a channel fed from a std 'Receiver'. the items are forwarded on
+    // a new thread, which closes the channel once every 'Sender' is dropped.
+
+    pub fn from_receiver(receiver: mpsc::Receiver<T>) -> Channel<T> {
+        let output = Channel::<T>::new();
+        let sender = output.clone();
+
+        thread::spawn(move || {
+            while let Ok(item) = receiver.recv() {
+                if sender.put(item).is_err() {
+                    break;
+                }
+            }
+
+            sender.close();
+        });
+
+        output
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a std 'Sender' whose items are put into this channel. they're
+    // forwarded on a new thread, which drops this handle once every 'Sender'
+    // is dropped or the channel is closed.
+
+    pub fn into_sender(self) -> mpsc::Sender<T> {
+        let (sender, receiver) = mpsc::channel::<T>();
+
+        thread::spawn(move || {
+            while let Ok(item) = receiver.recv() {
+                if self.put(item).is_err() {
+                    break;
+                }
+            }
+        });
+
+        sender
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn forward<U: Send + 'static>(
+        self,
+        mut func: impl FnMut(T) -> Option<U> + Send + 'static,
+    ) -> Channel<U> {
+        let output = Channel::<U>::new();
+        let sender = output.clone();
+
+        thread::spawn(move || {
+            while let Ok(item) = self.get() {
+                if let Some(item) = func(item)
+                    && sender.put(item).is_err()
+                {
+                    break;
+                }
+            }
+
+            sender.close();
+        });
+
+        output
+    }
+}
+
+impl<T: Send + 'static> Channel<T> {
+    // -----------------------------------------------------------------------
+    // returns a channel that receives the items of all the 'inputs', which is
+    // closed once none of them can 'get' any more. the inputs are watched
+    // with a single 'Select' on a new thread.
+
+    pub fn merge(inputs: Vec<Channel<T>>) -> Channel<T> {
+        let output = Channel::<T>::new();
+        let sender = output.clone();
+
+        thread::spawn(move || {
+            let mut inputs = inputs;
+
+            while !inputs.is_empty() {
+                let mut select = Select::new();
+
+                for input in inputs.iter() {
+                    select.add(input);
+                }
+
+                let Some(index) = select.wait() else {
+                    break;
+                };
+
+                let input = &inputs[index];
+
+                let sent = match input.get_timeout(Duration::ZERO) {
+                    Ok(item) => sender.put(item).is_ok() && sender.put_many(input.drain()).is_ok(),
+                    Err(RecvError::Timeout) => true,
+                    Err(_) => {
+                        inputs.swap_remove(index);
+                        true
+                    }
+                };
+
+                if !sent {
+                    break;
+                }
+            }
+
+            sender.close();
+        });
+
+        output
+    }
+}
+
+impl<T: Clone + Send + 'static> Channel<T> {
+    // -----------------------------------------------------------------------
+    // returns 'count' channels that each receive a clone of every item of this
+    // one. the items are copied out on a new thread, which closes the returned
+    // channels once this one can't 'get' any more. a closed output is skipped.
+
+    pub fn tee(self, count: usize) -> Vec<Channel<T>> {
+        let outputs: Vec<Channel<T>> = (0..count).map(|_| Channel::new()).collect();
+        let senders = outputs.clone();
+
+        thread::spawn(move || {
+            while let Ok(item) = self.get() {
+                let mut delivered = false;
+
+                for sender in senders.iter() {
+                    delivered |= sender.put(item.clone()).is_ok();
+                }
+
+                if !delivered {
+                    break;
+                }
+            }
+
+            for sender in senders.iter() {
+                sender.close();
+            }
+        });
+
+        outputs
+    }
+}
+
+impl<T> ChannelWait for Channel<T> {
+    // -----------------------------------------------------------------------
+
+    fn add_event(&self, event: Event<usize>, listener_id: usize) {
+        let mut state = self.data.lock();
+
+        if !state.items.is_empty() || self.data.is_closed() || self.data.is_disconnected() {
+            event.trigger();
+            return;
+        }
+
+        state.events.insert(listener_id, event);
+        self.data.update_listeners(&state);
+        self.data.collect_pending(&mut state);
+
+        if !state.items.is_empty() {
+            self.data.trigger_events(&mut state);
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn remove_event(&self, listener_id: usize) {
+        let mut state = self.data.lock();
+        state.events.remove(&listener_id);
+        self.data.update_listeners(&state);
+    }
+
+    // -----------------------------------------------------------------------
+    // a channel is ready if 'get' won't block: it has items, has ended, or
+    // has no other handle left that could put an item
+
+    fn is_ready(&self) -> bool {
+        let state = self.data.lock();
+        !state.items.is_empty() || self.data.is_closed() || self.data.is_disconnected()
+    }
+}
+
+impl<T> Default for Channel<T> {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        Channel::new()
+    }
+}
+
+impl<T> Clone for Channel<T> {
+    // -----------------------------------------------------------------------
+
+    fn clone(&self) -> Self {
+        let channel = Channel {
+            data: self.data.clone(),
+            instance_id: self.data.instance_counter.increment(),
+        };
+
+        channel.data.open_count.increment();
+        channel
+    }
+}
+
+impl<T> Drop for Channel<T> {
+    // -----------------------------------------------------------------------
+    // release the waiting consumers if they're the only handles left
+
+    fn drop(&mut self) {
+        let mut state = self.data.lock();
+        let open = self.data.open_count.decrement() - 1;
+        let waiting = self.data.wait_count.get();
+
+        if waiting == open {
+            self.data.release(&mut state);
+        }
+
+        // a select or pending 'recv_async' isn't counted as waiting, so it may
+        // now be on the only handle left
+
+        if waiting + 1 >= open {
+            self.data.trigger_events(&mut state);
+        }
+
+        // the callbacks run without the lock so they can use the channel
+
+        if open == 1 {
+            let callbacks = std::mem::take(&mut state.on_producers_dropped);
+            drop(state);
+
+            for callback in callbacks {
+                callback();
+            }
+        }
+    }
+}
+
+// ===========================================================================
+// ** RecvFuture **
+// ===========================================================================
+
+// a future that resolves with the channel's next item, or the reason 'get'
+// would have failed
+
+#[cfg(feature = "async")]
+pub struct RecvFuture<'a, T> {
+    channel: &'a Channel<T>,
+}
+
+#[cfg(feature = "async")]
+impl<T> Future for RecvFuture<'_, T> {
+    type Output = Result<T, RecvError>;
+
+    // -----------------------------------------------------------------------
+    // the waker is registered while the channel is locked so a concurrent
+    // 'put' can't slip in between the check & the registration
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let data = &self.channel.data;
+        let mut state = data.lock();
+
+        if let Some((_, item)) = state.pop() {
+            data.notify_producer();
+            return Poll::Ready(Ok(item));
+        }
+
+        if data.is_closed() {
+            return Poll::Ready(Err(RecvError::Closed));
+        }
+
+        if data.is_disconnected() {
+            return Poll::Ready(Err(data.disconnect_reason()));
+        }
+
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+            data.update_listeners(&state);
+        }
+
+        data.collect_pending(&mut state);
+
+        if let Some((_, item)) = state.pop() {
+            data.notify_producer();
+            return Poll::Ready(Ok(item));
+        }
+
+        Poll::Pending
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    // -----------------------------------------------------------------------
+    // test that 'put' on a full bounded channel waits for a 'get'
+
+    #[test]
+    fn validate_bounded_put() {
+        let channel = Channel::<i32>::bounded(2);
+        let producer = channel.clone();
+        assert_eq!(channel.capacity(), Some(2));
+
+        let handle = thread::spawn(move || {
+            for i in 0..4 {
+                producer.put(i).unwrap();
+            }
+        });
+
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(channel.len(), 2);
+
+        for i in 0..4 {
+            assert_eq!(channel.get(), Ok(i));
+        }
+
+        handle.join().unwrap();
+        assert_eq!(channel.len(), 0);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_get_timeout() {
+        let channel = Channel::<i32>::new();
+        let producer = channel.clone();
+        let start = Instant::now();
+        assert_eq!(
+            channel.get_timeout(Duration::from_millis(50)),
+            Err(RecvError::Timeout)
+        );
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            producer.put(7).unwrap();
+        });
+
+        assert_eq!(channel.get_timeout(Duration::from_secs(5)), Ok(7));
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_try_put() {
+        let channel = Channel::<i32>::bounded(1);
+        assert_eq!(channel.try_put(1), Ok(()));
+        assert_eq!(channel.try_put(2), Err(2));
+        assert_eq!(channel.get(), Ok(1));
+        assert_eq!(channel.try_put(3), Ok(()));
+
+        let unbounded = Channel::<i32>::new();
+        assert!((0..100).all(|i| unbounded.try_put(i).is_ok()));
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_peek() {
+        let channel = Channel::<String>::new();
+        assert_eq!(channel.peek(), None);
+
+        channel.put("first".to_string()).unwrap();
+        channel.put("second".to_string()).unwrap();
+        assert_eq!(channel.peek().unwrap(), "first");
+        assert_eq!(channel.len(), 2);
+        assert_eq!(channel.get().unwrap(), "first");
+        assert_eq!(channel.peek().unwrap(), "second");
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_status() {
+        let channel = Channel::<i32>::new();
+        let clone = channel.clone();
+        assert_eq!(channel.open_handles(), 2);
+        assert!(channel.is_empty());
+
+        clone.put(1).unwrap();
+        assert_eq!(channel.len(), 1);
+        assert!(!channel.is_ended());
+
+        clone.end();
+        drop(clone);
+        assert!(channel.is_ended());
+        assert_eq!(channel.open_handles(), 1);
+        assert_eq!(channel.get(), Ok(1));
+        assert_eq!(channel.get(), Err(RecvError::Closed));
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_drain() {
+        let channel = Channel::<i32>::bounded(3);
+
+        for i in 0..3 {
+            channel.put(i).unwrap();
+        }
+
+        assert_eq!(channel.drain(), vec![0, 1, 2]);
+        assert!(channel.is_empty());
+        assert!(channel.drain().is_empty());
+        assert_eq!(channel.try_put(3), Ok(()));
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_get_many() {
+        let channel = Channel::<i32>::new();
+        let producer = channel.clone();
+
+        for i in 0..5 {
+            producer.put(i).unwrap();
+        }
+
+        assert_eq!(channel.get_many(3), vec![0, 1, 2]);
+        assert_eq!(channel.get_many(10), vec![3, 4]);
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            producer.put(5).unwrap();
+        });
+
+        assert_eq!(channel.get_many(10), vec![5]);
+        handle.join().unwrap();
+        assert!(channel.get_many(10).is_empty());
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_put_many() {
+        let channel = Channel::<i32>::new();
+        channel.put_many(0..5).unwrap();
+        assert_eq!(channel.drain(), vec![0, 1, 2, 3, 4]);
+
+        let bounded = Channel::<i32>::bounded(2);
+        let consumer = bounded.clone();
+
+        let handle = thread::spawn(move || {
+            let mut items = Vec::new();
+
+            while items.len() < 6 {
+                items.extend(consumer.get_many(6));
+            }
+
+            items
+        });
+
+        bounded.put_many(vec![1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(handle.join().unwrap(), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_close() {
+        let channel = Channel::<i32>::bounded(1);
+        let producer = channel.clone();
+        channel.put(1).unwrap();
+
+        let handle = thread::spawn(move || producer.put(2));
+        thread::sleep(Duration::from_millis(20));
+        channel.close();
+
+        assert_eq!(handle.join().unwrap(), Err(SendError(2)));
+        assert_eq!(channel.put(3), Err(SendError(3)));
+        assert_eq!(channel.try_put(4), Err(4));
+        assert_eq!(channel.get(), Ok(1));
+        assert_eq!(channel.get(), Err(RecvError::Closed));
+
+        let lonely = Channel::<i32>::new();
+        assert_eq!(lonely.get(), Err(RecvError::AllSendersDropped));
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_stats() {
+        let channel = Channel::<i32>::named("telemetry");
+        channel.put_many(0..4).unwrap();
+        channel.get().unwrap();
+        channel.put(4).unwrap();
+
+        let stats = channel.stats();
+        assert_eq!(stats.name, "telemetry");
+        assert_eq!(stats.put_count, 5);
+        assert_eq!(stats.get_count, 1);
+        assert_eq!(stats.depth, 4);
+        assert_eq!(stats.high_water, 4);
+
+        channel.drain();
+        assert_eq!(channel.stats().get_count, 5);
+        assert_eq!(channel.stats().depth, 0);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn validate_recv_async() {
+        use crate::thread::block_on;
+
+        let channel = Channel::<i32>::new();
+        let producer = channel.clone();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            producer.put(1).unwrap();
+            thread::sleep(Duration::from_millis(20));
+        });
+
+        assert_eq!(block_on(channel.recv_async()), Ok(1));
+        assert_eq!(
+            block_on(channel.recv_async()),
+            Err(RecvError::AllSendersDropped)
+        );
+        handle.join().unwrap();
+
+        channel.put(2).unwrap();
+        channel.close();
+        assert_eq!(block_on(channel.recv_async()), Ok(2));
+        assert_eq!(block_on(channel.recv_async()), Err(RecvError::Closed));
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_rendezvous() {
+        let channel = Channel::<i32>::rendezvous();
+        let producer = channel.clone();
+        assert_eq!(channel.capacity(), Some(0));
+        assert_eq!(channel.try_put(1), Err(1));
+
+        let start = Instant::now();
+        let handle = thread::spawn(move || {
+            producer.put(2).unwrap();
+            start.elapsed()
+        });
+
+        thread::sleep(Duration::from_millis(50));
+        assert!(channel.is_empty());
+        assert_eq!(channel.get(), Ok(2));
+        assert!(handle.join().unwrap() >= Duration::from_millis(50));
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_sharded() {
+        let channel = Channel::<(usize, i32)>::sharded(4);
+        let mut handles = Vec::new();
+
+        for producer_id in 0..4 {
+            let producer = channel.clone();
+
+            handles.push(thread::spawn(move || {
+                for i in 0..100 {
+                    producer.put((producer_id, i)).unwrap();
+                }
+            }));
+        }
+
+        let mut next = [0; 4];
+
+        for _ in 0..400 {
+            let (producer_id, i) = channel.get().unwrap();
+            assert_eq!(next[producer_id], i);
+            next[producer_id] += 1;
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert!(channel.is_empty());
+        assert_eq!(channel.stats().put_count, 400);
+    }
+
+    // -----------------------------------------------------------------------
+    // test that an unbounded channel's lock-free puts keep each producer's
+    // order & wake a consumer blocked in 'get'
+
+    #[test]
+    fn validate_inbox() {
+        let channel = Channel::<(usize, i32)>::new();
+        let consumer = channel.clone();
+        let first = thread::spawn(move || consumer.get().unwrap());
+
+        thread::sleep(Duration::from_millis(50));
+        channel.put((9, 0)).unwrap();
+        assert_eq!(first.join().unwrap(), (9, 0));
+
+        let mut handles = Vec::new();
+
+        for producer_id in 0..4 {
+            let producer = channel.clone();
+
+            handles.push(thread::spawn(move || {
+                for i in 0..100 {
+                    producer.put((producer_id, i)).unwrap();
+                }
+
+                producer
+                    .put_many((100..110).map(|i| (producer_id, i)))
+                    .unwrap();
+            }));
+        }
+
+        let mut next = [0; 4];
+
+        for _ in 0..440 {
+            let (producer_id, i) = channel.get().unwrap();
+            assert_eq!(next[producer_id], i);
+            next[producer_id] += 1;
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        channel.close();
+        assert_eq!(channel.put((0, 0)), Err(SendError((0, 0))));
+        assert_eq!(channel.stats().put_count, 441);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_overflow_policy() {
+        let newest = Channel::<i32>::bounded_with(2, OverflowPolicy::DropNewest);
+        newest.put_many(0..4).unwrap();
+        assert_eq!(newest.put(4), Ok(()));
+        assert_eq!(newest.drain(), vec![0, 1]);
+        assert_eq!(newest.stats().dropped_count, 3);
+
+        let oldest = Channel::<i32>::bounded_with(2, OverflowPolicy::DropOldest);
+        oldest.put_many(0..4).unwrap();
+        oldest.put(4).unwrap();
+        assert_eq!(oldest.drain(), vec![3, 4]);
+        assert_eq!(oldest.stats().dropped_count, 3);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_map_filter() {
+        let input = Channel::<i32>::new();
+        let output = input
+            .clone()
+            .filter(|x| x % 2 == 0)
+            .map(|x| format!("#{x}"));
+
+        input.put_many(0..6).unwrap();
+        input.close();
+
+        let items: Vec<String> = std::iter::from_fn(|| output.get().ok()).collect();
+        assert_eq!(items, vec!["#0", "#2", "#4"]);
+        assert_eq!(output.get(), Err(RecvError::Closed));
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_tee() {
+        let input = Channel::<i32>::new();
+        let outputs = input.clone().tee(2);
+
+        input.put_many(1..=3).unwrap();
+        input.close();
+
+        for output in outputs {
+            let items: Vec<i32> = std::iter::from_fn(|| output.get().ok()).collect();
+            assert_eq!(items, vec![1, 2, 3]);
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_merge() {
+        let first = Channel::<i32>::new();
+        let second = Channel::<i32>::new();
+        let output = Channel::merge(vec![first.clone(), second.clone()]);
+
+        first.put_many(0..3).unwrap();
+        first.close();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            second.put(10).unwrap();
+        });
+
+        let mut items: Vec<i32> = std::iter::from_fn(|| output.get().ok()).collect();
+        items.sort();
+        assert_eq!(items, vec![0, 1, 2, 10]);
+        assert_eq!(output.get(), Err(RecvError::Closed));
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_put_after_end() {
+        let channel = Channel::<i32>::sharded(2);
+        channel.put(1).unwrap();
+        channel.end();
+
+        assert_eq!(channel.put(2), Err(SendError(2)));
+        assert_eq!(channel.put_many(vec![3, 4]), Err(SendError(vec![3, 4])));
+        assert_eq!(channel.try_put(5), Err(5));
+        assert_eq!(channel.get(), Ok(1));
+        assert_eq!(channel.get(), Err(RecvError::Closed));
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_idle_timeout() {
+        let channel = Channel::<i32>::new();
+        let producer = channel.clone();
+        channel.set_idle_timeout(Duration::from_millis(50));
+
+        let handle = thread::spawn(move || {
+            for i in 0..3 {
+                thread::sleep(Duration::from_millis(20));
+                producer.put(i).unwrap();
+            }
+
+            thread::sleep(Duration::from_millis(100));
+            producer.put(3)
+        });
+
+        let start = Instant::now();
+        let items: Vec<i32> = std::iter::from_fn(|| channel.get().ok()).collect();
+        assert_eq!(items, vec![0, 1, 2]);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert!(channel.is_ended());
+        assert_eq!(handle.join().unwrap(), Err(SendError(3)));
+    }
+
+    // -----------------------------------------------------------------------
+    // test that steady puts keep an inbox channel open across several idle
+    // periods, even while the consumer still has older items to take
+
+    #[test]
+    fn validate_idle_timeout_inbox() {
+        let channel = Channel::<i32>::new();
+        channel.set_idle_timeout(Duration::from_millis(50));
+        channel.put_many(0..5).unwrap();
+        assert_eq!(channel.get(), Ok(0));
+
+        for i in 5..20 {
+            thread::sleep(Duration::from_millis(20));
+            channel.put(i).unwrap();
+            assert_eq!(channel.get(), Ok(i - 4));
+        }
+
+        assert!(!channel.is_ended());
+        assert_eq!(channel.stats().put_count, 20);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_snapshot_debug() {
+        let channel = Channel::<(i32, &str)>::new();
+        channel.put_many([(1, "a"), (2, "b"), (3, "c")]).unwrap();
+
+        assert_eq!(channel.snapshot_debug(2), vec!["(1, \"a\")", "(2, \"b\")"]);
+        assert_eq!(channel.snapshot_debug(10).len(), 3);
+        assert_eq!(channel.len(), 3);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_get_deadline() {
+        let first = Channel::<i32>::new();
+        let second = Channel::<i32>::new();
+        let _producers = (first.clone(), second.clone());
+        let deadline = Instant::now() + Duration::from_millis(60);
+
+        first.put(1).unwrap();
+        assert_eq!(first.get_deadline(deadline), Ok(1));
+        assert_eq!(first.get_deadline(deadline), Err(RecvError::Timeout));
+        assert_eq!(second.get_deadline(deadline), Err(RecvError::Timeout));
+        assert!(Instant::now() >= deadline);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_get_tagged() {
+        let channel = Channel::<&str>::sharded(2);
+        let first = channel.clone();
+        let second = channel.clone();
+        assert_ne!(first.producer_id(), second.producer_id());
+
+        first.put("a").unwrap();
+        second.put_many(["b"]).unwrap();
+
+        let mut tagged = vec![channel.get_tagged().unwrap(), channel.get_tagged().unwrap()];
+        tagged.sort_by_key(|(_, item)| *item);
+        assert_eq!(
+            tagged,
+            vec![(first.producer_id(), "a"), (second.producer_id(), "b")]
+        );
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_on_all_producers_dropped() {
+        let input = Channel::<i32>::new();
+        let downstream = Channel::<i32>::new();
+        let first = input.clone();
+        let second = input.clone();
+
+        let sender = downstream.clone();
+        input.on_all_producers_dropped(move || sender.close());
+
+        first.put(1).unwrap();
+        drop(first);
+        assert!(!downstream.is_ended());
+
+        let handle = thread::spawn(move || drop(second));
+        handle.join().unwrap();
+        assert!(downstream.is_ended());
+        assert_eq!(input.get(), Ok(1));
+
+        let called = Arc::new(AtomicInteger::new(0));
+        let count = called.clone();
+        input.on_all_producers_dropped(move || {
+            count.increment();
+        });
+        assert_eq!(called.get(), 1);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_mpsc_interop() {
+        let (sender, receiver) = mpsc::channel::<i32>();
+        let channel = Channel::from_receiver(receiver);
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        drop(sender);
+
+        assert_eq!(channel.get(), Ok(1));
+        assert_eq!(channel.get(), Ok(2));
+        assert_eq!(channel.get(), Err(RecvError::Closed));
+
+        let channel = Channel::<i32>::new();
+        let sender = channel.clone().into_sender();
+        sender.send(3).unwrap();
+        drop(sender);
+
+        assert_eq!(channel.get(), Ok(3));
+        assert_eq!(channel.get(), Err(RecvError::AllSendersDropped));
+    }
+
+    // -----------------------------------------------------------------------
+    // test that 'get' reports when every handle is blocked in 'get'
+
+    #[test]
+    fn validate_would_deadlock() {
+        let channel = Channel::<i32>::new();
+        let clone = channel.clone();
+
+        let handle = thread::spawn(move || clone.get());
+        thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(channel.get(), Err(RecvError::WouldDeadlock));
+        assert_eq!(handle.join().unwrap(), Err(RecvError::WouldDeadlock));
+        assert_eq!(channel.get(), Err(RecvError::AllSendersDropped));
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_put_timeout() {
+        let channel = Channel::<i32>::bounded(1);
+        let consumer = channel.clone();
+        channel.put(1).unwrap();
+
+        let start = Instant::now();
+        assert_eq!(channel.put_timeout(2, Duration::from_millis(50)), Err(2));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            consumer.get()
+        });
+
+        assert_eq!(channel.put_timeout(3, Duration::from_secs(5)), Ok(()));
+        assert_eq!(handle.join().unwrap(), Ok(1));
+        assert_eq!(channel.get(), Ok(3));
     }
 }
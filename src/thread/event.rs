@@ -1,55 +1,308 @@
 use crate::thread::AtomicInteger;
-use std::collections::VecDeque;
+use crate::thread::OverflowPolicy;
+use crate::thread::channel::ChannelWait;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+
+// ===========================================================================
+// ** EventQueue **
+// ===========================================================================
+
+// the triggered values, highest priority first & in trigger order within a
+// priority
+
+struct EventQueue<T> {
+    entries: VecDeque<(u8, T)>,
+}
+
+impl<T> EventQueue<T> {
+    // -----------------------------------------------------------------------
+
+    fn new() -> Self {
+        EventQueue {
+            entries: VecDeque::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // -----------------------------------------------------------------------
+    // queue 'value' behind the values with the same or a higher priority
+
+    fn push(&mut self, priority: u8, value: T) {
+        let index = self
+            .entries
+            .partition_point(|(queued, _)| *queued >= priority);
+
+        self.entries.insert(index, (priority, value));
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn pop_front(&mut self) -> Option<T> {
+        self.entries.pop_front().map(|(_, value)| value)
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn remove(&mut self, index: usize) -> Option<T> {
+        self.entries.remove(index).map(|(_, value)| value)
+    }
+
+    // -----------------------------------------------------------------------
+    // drop the oldest of the lowest priority values to make room
+
+    fn drop_oldest(&mut self) {
+        if let Some(&(lowest, _)) = self.entries.back() {
+            let index = self.entries.partition_point(|(queued, _)| *queued > lowest);
+            self.entries.remove(index);
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // remove & return up to 'count' values from the front
+
+    fn take(&mut self, count: usize) -> Vec<T> {
+        let count = count.min(self.entries.len());
+        self.entries
+            .drain(..count)
+            .map(|(_, value)| value)
+            .collect()
+    }
+}
 
 // ===========================================================================
 // ** SharedData **
 // ===========================================================================
 
+// 'coalesce' compares values for a coalescing listener, which skips a value
+// that is already queued. 'bound' limits the queue of a bounded listener,
+// whose blocked triggers wait on 'space' & drop their value if the listener
+// goes away. 'selects' holds the events of the
+// selects waiting on the listener.
+
 struct SharedData<T> {
     trigger: Condvar,
-    triggered_events: Mutex<VecDeque<T>>,
+    space: Condvar,
+    triggered_events: Mutex<EventQueue<T>>,
     event_count: AtomicInteger,
+    listening: AtomicBool,
+    coalesce: Option<fn(&T, &T) -> bool>,
+    bound: Option<(usize, OverflowPolicy)>,
+    selects: Mutex<HashMap<usize, Event<usize>>>,
+    #[cfg(feature = "async")]
+    wakers: Mutex<Vec<Waker>>,
 }
 
 impl<T> SharedData<T> {
     // -----------------------------------------------------------------------
 
-    pub fn new() -> Self {
+    pub fn new(
+        coalesce: Option<fn(&T, &T) -> bool>,
+        bound: Option<(usize, OverflowPolicy)>,
+    ) -> Self {
         SharedData {
             trigger: Condvar::new(),
-            triggered_events: Mutex::new(VecDeque::new()),
+            space: Condvar::new(),
+            triggered_events: Mutex::new(EventQueue::new()),
             event_count: AtomicInteger::new(0),
+            listening: AtomicBool::new(true),
+            coalesce,
+            bound,
+            selects: Mutex::new(HashMap::new()),
+            #[cfg(feature = "async")]
+            wakers: Mutex::new(Vec::new()),
         }
     }
 
     // -----------------------------------------------------------------------
 
-    pub fn trigger(&self, value: T) {
+    pub fn trigger(&self, priority: u8, value: T) {
         let mut lock = self.triggered_events.lock().unwrap();
-        lock.push_back(value);
+
+        if let Some(eq) = self.coalesce
+            && lock.iter().any(|queued| eq(queued, &value))
+        {
+            return;
+        }
+
+        if let Some((capacity, overflow)) = self.bound
+            && lock.len() >= capacity
+        {
+            match overflow {
+                OverflowPolicy::DropNewest => return,
+                OverflowPolicy::DropOldest => lock.drop_oldest(),
+                OverflowPolicy::Block => {
+                    lock = self
+                        .space
+                        .wait_while(lock, |events| {
+                            events.len() >= capacity && self.listening.load(Ordering::Acquire)
+                        })
+                        .unwrap();
+
+                    if !self.listening.load(Ordering::Acquire) {
+                        return;
+                    }
+                }
+            }
+        }
+
+        lock.push(priority, value);
+        self.notify();
+    }
+
+    // -----------------------------------------------------------------------
+    // wake the triggers blocked on a full bounded queue once values are taken
+
+    fn taken(&self) {
+        if self.bound.is_some() {
+            self.space.notify_all();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // wake the waiters once the last event has been dropped, since no event
+    // can fire any more
+
+    fn release(&self) {
+        let _lock = self.triggered_events.lock().unwrap();
+        self.notify();
+    }
+
+    // -----------------------------------------------------------------------
+    // wake the blocked waits, the selects & the tasks polling the listener.
+    // called with 'triggered_events' locked.
+
+    fn notify(&self) {
         self.trigger.notify_all();
+
+        for (_, event) in self.selects.lock().unwrap().drain() {
+            event.trigger();
+        }
+
+        #[cfg(feature = "async")]
+        for waker in self.wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
     }
 
     // -----------------------------------------------------------------------
+    // the waker is registered while the queue is locked so a concurrent
+    // trigger can't slip in between the check & the registration
 
-    pub fn wait_one(&self) -> Option<T> {
+    #[cfg(feature = "async")]
+    fn poll_next(&self, cx: &mut Context<'_>) -> Poll<Option<T>> {
         let mut lock = self.triggered_events.lock().unwrap();
 
-        if lock.len() > 0 {
-            return lock.pop_front();
+        if let Some(value) = lock.pop_front() {
+            self.taken();
+            return Poll::Ready(Some(value));
         }
 
         if self.event_count.get() < 1 {
-            return None;
+            return Poll::Ready(None);
+        }
+
+        let mut wakers = self.wakers.lock().unwrap();
+
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
         }
 
-        let mut lock = self.trigger.wait(lock).unwrap();
+        Poll::Pending
+    }
+
+    // -----------------------------------------------------------------------
+    // returns 'true' if 'wait_one' wouldn't block
+
+    fn is_ready(&self, events: &EventQueue<T>) -> bool {
+        !events.is_empty() || self.event_count.get() < 1
+    }
+
+    // -----------------------------------------------------------------------
+    // returns 'true' while nothing has fired but some event still could
+
+    fn is_pending(&self, events: &EventQueue<T>) -> bool {
+        events.is_empty() && self.event_count.get() > 0
+    }
+
+    // -----------------------------------------------------------------------
+
+    // the wait loops on 'is_pending' so a spurious wakeup doesn't end it
+    // early with 'None'
+
+    pub fn wait_one(&self) -> Option<T> {
+        let lock = self.triggered_events.lock().unwrap();
+        let mut lock = self
+            .trigger
+            .wait_while(lock, |events| self.is_pending(events))
+            .unwrap();
+
+        self.taken();
         lock.pop_front()
     }
 
     // -----------------------------------------------------------------------
 
+    pub fn wait_one_timeout(&self, timeout: Duration) -> Option<T> {
+        let lock = self.triggered_events.lock().unwrap();
+        let (mut lock, _) = self
+            .trigger
+            .wait_timeout_while(lock, timeout, |events| self.is_pending(events))
+            .unwrap();
+
+        self.taken();
+        lock.pop_front()
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn wait_one_where(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<T> {
+        let mut lock = self.triggered_events.lock().unwrap();
+
+        loop {
+            let index = lock.iter().position(&mut predicate);
+
+            if let Some(index) = index {
+                self.taken();
+                return lock.remove(index);
+            }
+
+            if self.event_count.get() < 1 {
+                return None;
+            }
+
+            lock = self.trigger.wait(lock).unwrap();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
     pub fn wait_some(&self) -> Vec<T> {
         let mut values = Vec::<T>::new();
 
@@ -62,6 +315,7 @@ impl<T> SharedData<T> {
                     values.push(lock.pop_front().unwrap());
                 }
 
+                self.taken();
                 return values;
             }
         }
@@ -75,6 +329,34 @@ impl<T> SharedData<T> {
 
     // -----------------------------------------------------------------------
 
+    pub fn wait_some_timeout(&self, timeout: Duration) -> Vec<T> {
+        let lock = self.triggered_events.lock().unwrap();
+        let (mut lock, _) = self
+            .trigger
+            .wait_timeout_while(lock, timeout, |events| self.is_pending(events))
+            .unwrap();
+
+        self.taken();
+        lock.take(usize::MAX)
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn wait_n(&self, count: usize) -> Vec<T> {
+        let lock = self.triggered_events.lock().unwrap();
+        let mut lock = self
+            .trigger
+            .wait_while(lock, |events| {
+                events.len() < count && self.event_count.get() > 0
+            })
+            .unwrap();
+
+        self.taken();
+        lock.take(count)
+    }
+
+    // -----------------------------------------------------------------------
+
     pub fn wait_all(&self) -> Vec<T> {
         let mut values = Vec::<T>::new();
 
@@ -96,31 +378,153 @@ impl<T> SharedData<T> {
 // ** Event **
 // ===========================================================================
 
-pub struct Event<T: Copy> {
+// the value is moved to the listener when the event is triggered, so it can
+// carry any payload, not just an index into a side table
+
+pub struct Event<T> {
+    shared: Arc<SharedData<T>>,
+    value: Option<T>,
+    priority: u8,
+}
+
+impl<T> Event<T> {
+    // -----------------------------------------------------------------------
+
+    fn new(shared: Arc<SharedData<T>>, value: T, priority: u8) -> Self {
+        shared.event_count.increment();
+        Event {
+            shared,
+            value: Some(value),
+            priority,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn trigger(mut self) {
+        if let Some(value) = self.value.take() {
+            self.shared.trigger(self.priority, value);
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // give up on the event without firing it. the listener stops counting it,
+    // & its waits return once no other event is outstanding. returns 'false'
+    // if the listener was already dropped, so nobody was waiting on it.
+
+    pub fn revoke(self) -> bool {
+        self.shared.listening.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for Event<T> {
+    // -----------------------------------------------------------------------
+
+    fn drop(&mut self) {
+        if self.shared.event_count.decrement() == 1 {
+            self.shared.release();
+        }
+    }
+}
+
+// ===========================================================================
+// ** RecurringEvent **
+// ===========================================================================
+
+// an event that can be triggered any number of times, queueing a clone of its
+// value each time. it counts as outstanding until it is revoked or dropped,
+// so the listener keeps waiting for it until then.
+
+pub struct RecurringEvent<T: Clone> {
     shared: Arc<SharedData<T>>,
     value: T,
 }
 
-impl<T: Copy> Event<T> {
+impl<T: Clone> RecurringEvent<T> {
     // -----------------------------------------------------------------------
 
     fn new(shared: Arc<SharedData<T>>, value: T) -> Self {
         shared.event_count.increment();
-        Event { shared, value }
+        RecurringEvent { shared, value }
     }
 
     // -----------------------------------------------------------------------
 
-    pub fn trigger(self) {
-        self.shared.trigger(self.value);
+    pub fn trigger(&self) {
+        self.shared.trigger(0, self.value.clone());
+    }
+
+    // -----------------------------------------------------------------------
+    // stop the event for good. like 'Event::revoke', returns 'false' if the
+    // listener was already dropped.
+
+    pub fn revoke(self) -> bool {
+        self.shared.listening.load(Ordering::Acquire)
     }
 }
 
-impl<T: Copy> Drop for Event<T> {
+impl<T: Clone> Drop for RecurringEvent<T> {
     // -----------------------------------------------------------------------
 
     fn drop(&mut self) {
-        self.shared.event_count.decrement();
+        if self.shared.event_count.decrement() == 1 {
+            self.shared.release();
+        }
+    }
+}
+
+// ===========================================================================
+// ** EventDispatcher **
+// ===========================================================================
+
+// fans one trigger out to several listeners. each subscriber adds an event
+// created by its own listener, with its own value, & all of them fire
+// together. dropping the dispatcher without triggering drops the events.
+
+pub struct EventDispatcher<T> {
+    events: Vec<Event<T>>,
+}
+
+impl<T> EventDispatcher<T> {
+    // -----------------------------------------------------------------------
+
+    pub fn new() -> Self {
+        EventDispatcher { events: Vec::new() }
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn add(&mut self, event: Event<T>) {
+        self.events.push(event);
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    // -----------------------------------------------------------------------
+    // trigger every event that was added
+
+    pub fn trigger(self) {
+        for event in self.events {
+            event.trigger();
+        }
+    }
+}
+
+impl<T> Default for EventDispatcher<T> {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        EventDispatcher::new()
     }
 }
 
@@ -128,23 +532,74 @@ impl<T: Copy> Drop for Event<T> {
 // ** EventListner **
 // ===========================================================================
 
-pub struct EventListener<T: Copy> {
+pub struct EventListener<T> {
     shared: Arc<SharedData<T>>,
 }
 
-impl<T: Copy> EventListener<T> {
+impl<T> EventListener<T> {
     // -----------------------------------------------------------------------
 
     pub fn new() -> Self {
         EventListener {
-            shared: Arc::new(SharedData::new()),
+            shared: Arc::new(SharedData::new(None, None)),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // create a listener that collapses repeated values: a value that fires
+    // while an equal one is still queued is dropped, so a busy 'dirty'
+    // notification wakes the consumer once instead of flooding the queue
+
+    pub fn coalescing() -> Self
+    where
+        T: PartialEq,
+    {
+        EventListener {
+            shared: Arc::new(SharedData::new(Some(T::eq), None)),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // create a listener that queues at most 'capacity' triggered values, where
+    // 'overflow' decides what a trigger does while the queue is full.
+    // 'DropOldest' drops the oldest of the lowest priority values.
+
+    pub fn bounded(capacity: usize, overflow: OverflowPolicy) -> Self {
+        assert!(capacity > 0, "capacity must be at least 1");
+
+        EventListener {
+            shared: Arc::new(SharedData::new(None, Some((capacity, overflow)))),
         }
     }
 
     // -----------------------------------------------------------------------
 
     pub fn create_event(&mut self, value: T) -> Event<T> {
-        Event::new(self.shared.clone(), value)
+        Event::new(self.shared.clone(), value, 0)
+    }
+
+    // -----------------------------------------------------------------------
+    // create an event whose value is returned ahead of the queued values with
+    // a lower priority. 'create_event' uses priority 0.
+
+    pub fn create_event_with_priority(&mut self, value: T, priority: u8) -> Event<T> {
+        Event::new(self.shared.clone(), value, priority)
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn create_recurring_event(&mut self, value: T) -> RecurringEvent<T>
+    where
+        T: Clone,
+    {
+        RecurringEvent::new(self.shared.clone(), value)
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the number of events that haven't fired, been revoked or dropped
+
+    pub fn outstanding(&self) -> usize {
+        self.shared.event_count.get() as usize
     }
 
     // -----------------------------------------------------------------------
@@ -153,17 +608,144 @@ impl<T: Copy> EventListener<T> {
         self.shared.wait_one()
     }
 
+    // -----------------------------------------------------------------------
+    // like 'wait_one' but returns 'None' if no event fires within 'timeout',
+    // or as soon as every event has been dropped without firing
+
+    pub fn wait_one_timeout(&self, timeout: Duration) -> Option<T> {
+        self.shared.wait_one_timeout(timeout)
+    }
+
+    // -----------------------------------------------------------------------
+    // block until an event whose value matches 'predicate' fires & return it.
+    // values that don't match stay queued for the other waits. returns 'None'
+    // once no queued value matches & no event is left to fire.
+
+    pub fn wait_one_where(&self, predicate: impl FnMut(&T) -> bool) -> Option<T> {
+        self.shared.wait_one_where(predicate)
+    }
+
     // -----------------------------------------------------------------------
 
     pub fn wait_some(&self) -> Vec<T> {
         self.shared.wait_some()
     }
 
+    // -----------------------------------------------------------------------
+    // like 'wait_some' but returns an empty list if no event fires within
+    // 'timeout', or as soon as every event has been dropped without firing
+
+    pub fn wait_some_timeout(&self, timeout: Duration) -> Vec<T> {
+        self.shared.wait_some_timeout(timeout)
+    }
+
+    // -----------------------------------------------------------------------
+    // block until 'count' events have fired & return them, leaving any others
+    // queued. if the events still outstanding can't make up 'count', returns
+    // the ones that fired once the rest have been dropped.
+
+    pub fn wait_n(&self, count: usize) -> Vec<T> {
+        self.shared.wait_n(count)
+    }
+
     // -----------------------------------------------------------------------
 
     pub fn wait_all(&self) -> Vec<T> {
         self.shared.wait_all()
     }
+
+    // -----------------------------------------------------------------------
+    // poll for the next triggered value without blocking, with the same shape
+    // as 'Stream::poll_next'. 'Ready(None)' means no event is left to fire.
+
+    #[cfg(feature = "async")]
+    pub fn poll_next(&self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.shared.poll_next(cx)
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a future that resolves like 'wait_one', without blocking the
+    // thread, so async code can loop on 'listener.next_async().await'
+
+    #[cfg(feature = "async")]
+    pub fn next_async(&self) -> NextEvent<'_, T> {
+        NextEvent { listener: self }
+    }
+}
+
+impl<T> Default for EventListener<T> {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        EventListener::new()
+    }
+}
+
+impl<T> Drop for EventListener<T> {
+    // -----------------------------------------------------------------------
+    // let the events that outlive the listener know nobody is waiting, &
+    // release the triggers blocked on a full bounded queue
+
+    fn drop(&mut self) {
+        let _lock = self.shared.triggered_events.lock().unwrap();
+        self.shared.listening.store(false, Ordering::Release);
+        self.shared.space.notify_all();
+    }
+}
+
+// lets a listener be added to a 'Select', so listeners of different value
+// types can be waited on together. a listener is ready when 'wait_one'
+// wouldn't block.
+
+impl<T> ChannelWait for EventListener<T> {
+    // -----------------------------------------------------------------------
+
+    fn add_event(&self, event: Event<usize>, listener_id: usize) {
+        let lock = self.shared.triggered_events.lock().unwrap();
+
+        if self.shared.is_ready(&lock) {
+            event.trigger();
+        } else {
+            let mut selects = self.shared.selects.lock().unwrap();
+            selects.insert(listener_id, event);
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn remove_event(&self, listener_id: usize) {
+        self.shared.selects.lock().unwrap().remove(&listener_id);
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn is_ready(&self) -> bool {
+        let lock = self.shared.triggered_events.lock().unwrap();
+        self.shared.is_ready(&lock)
+    }
+}
+
+// ===========================================================================
+// ** NextEvent **
+// ===========================================================================
+
+// a future that resolves with the listener's next triggered value, or 'None'
+// once no event is left to fire
+
+#[cfg(feature = "async")]
+pub struct NextEvent<'a, T> {
+    listener: &'a EventListener<T>,
+}
+
+#[cfg(feature = "async")]
+impl<T> Future for NextEvent<'_, T> {
+    type Output = Option<T>;
+
+    // -----------------------------------------------------------------------
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.listener.poll_next(cx)
+    }
 }
 
 // ===========================================================================
@@ -173,13 +755,15 @@ impl<T: Copy> EventListener<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::thread;
+    use std::time::Instant;
 
     // -----------------------------------------------------------------------
 
     #[test]
     fn wait_one() {
         let mut listener = EventListener::<usize>::new();
-        let mut triggered_indices = vec![false; 10];
+        let mut triggered_indices = [false; 10];
         let mut triggered_count = 0;
 
         for i in 0..triggered_indices.len() {
@@ -187,16 +771,12 @@ mod tests {
             event.trigger();
         }
 
-        loop {
-            if let Some(index) = listener.wait_one() {
-                triggered_indices[index] = true;
-                triggered_count += 1;
+        while let Some(index) = listener.wait_one() {
+            triggered_indices[index] = true;
+            triggered_count += 1;
 
-                if triggered_count == 10 {
-                    println!("all triggers fired");
-                    break;
-                }
-            } else {
+            if triggered_count == 10 {
+                println!("all triggers fired");
                 break;
             }
         }
@@ -204,13 +784,37 @@ mod tests {
         assert!(triggered_count == 10);
     }
 
+    // -----------------------------------------------------------------------
+    // a spurious wakeup doesn't end 'wait_one' before an event fires
+
+    #[test]
+    fn wait_one_spurious() {
+        let mut listener = EventListener::<usize>::new();
+        let event = listener.create_event(7);
+        let shared = listener.shared.clone();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+
+            {
+                let _lock = shared.triggered_events.lock().unwrap();
+                shared.trigger.notify_all();
+            }
+
+            thread::sleep(Duration::from_millis(20));
+            event.trigger();
+        });
+
+        assert_eq!(listener.wait_one(), Some(7));
+        handle.join().unwrap();
+    }
     // -----------------------------------------------------------------------
 
     #[test]
     fn wait_one_no_events() {
         let listener = EventListener::<usize>::new();
         let event_value = listener.wait_one();
-        assert!(event_value == None);
+        assert!(event_value.is_none());
     }
 
     // -----------------------------------------------------------------------
@@ -230,4 +834,303 @@ mod tests {
         let event_values = listener.wait_all();
         assert!(event_values.is_empty());
     }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn wait_one_timeout() {
+        let mut listener = EventListener::<usize>::new();
+        let event = listener.create_event(3);
+        assert!(
+            listener
+                .wait_one_timeout(Duration::from_millis(20))
+                .is_none()
+        );
+
+        event.trigger();
+        assert_eq!(
+            listener.wait_one_timeout(Duration::from_millis(20)),
+            Some(3)
+        );
+        assert!(listener.wait_one_timeout(Duration::from_secs(5)).is_none());
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn wait_some_timeout() {
+        let mut listener = EventListener::<usize>::new();
+        let first = listener.create_event(1);
+        let second = listener.create_event(2);
+        let unused = listener.create_event(3);
+        assert!(
+            listener
+                .wait_some_timeout(Duration::from_millis(20))
+                .is_empty()
+        );
+
+        first.trigger();
+        second.trigger();
+        assert_eq!(
+            listener.wait_some_timeout(Duration::from_millis(20)),
+            [1, 2]
+        );
+
+        // dropping the last event ends the wait without the full timeout
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            drop(unused);
+        });
+
+        let start = Instant::now();
+        assert!(
+            listener
+                .wait_some_timeout(Duration::from_secs(5))
+                .is_empty()
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn wait_n() {
+        let mut listener = EventListener::<usize>::new();
+        let events: Vec<_> = (0..5).map(|i| listener.create_event(i)).collect();
+
+        let handle = thread::spawn(move || {
+            for event in events.into_iter().take(4) {
+                thread::sleep(Duration::from_millis(5));
+                event.trigger();
+            }
+        });
+
+        let mut quorum = listener.wait_n(3);
+        quorum.sort();
+        assert_eq!(quorum, [0, 1, 2]);
+        handle.join().unwrap();
+
+        // only one event fired & the rest are gone
+
+        assert_eq!(listener.wait_n(3), [3]);
+        assert!(listener.wait_n(1).is_empty());
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn owned_payload() {
+        let mut listener = EventListener::<String>::new();
+        let event = listener.create_event("replica-2".to_string());
+
+        thread::spawn(move || event.trigger()).join().unwrap();
+        assert_eq!(listener.wait_one().as_deref(), Some("replica-2"));
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn recurring_event() {
+        let mut listener = EventListener::<usize>::new();
+        let ticks = listener.create_recurring_event(7);
+
+        let handle = thread::spawn(move || {
+            for _ in 0..3 {
+                ticks.trigger();
+                thread::sleep(Duration::from_millis(5));
+            }
+
+            assert!(ticks.revoke());
+        });
+
+        assert_eq!(listener.wait_all(), [7, 7, 7]);
+        handle.join().unwrap();
+        assert!(listener.wait_one().is_none());
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn wait_one_where() {
+        let mut listener = EventListener::<usize>::new();
+        let events: Vec<_> = (0..4).map(|i| listener.create_event(i)).collect();
+
+        let handle = thread::spawn(move || {
+            for event in events {
+                thread::sleep(Duration::from_millis(5));
+                event.trigger();
+            }
+        });
+
+        assert_eq!(listener.wait_one_where(|value| *value == 3), Some(3));
+        handle.join().unwrap();
+
+        assert!(listener.wait_one_where(|value| *value > 3).is_none());
+        assert_eq!(listener.wait_all(), [0, 1, 2]);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn dispatcher() {
+        let mut cache = EventListener::<&str>::new();
+        let mut metrics = EventListener::<&str>::new();
+        let mut dispatcher = EventDispatcher::new();
+        dispatcher.add(cache.create_event("invalidate"));
+        dispatcher.add(metrics.create_event("count"));
+        assert_eq!(dispatcher.len(), 2);
+
+        thread::spawn(move || dispatcher.trigger()).join().unwrap();
+        assert_eq!(cache.wait_all(), ["invalidate"]);
+        assert_eq!(metrics.wait_all(), ["count"]);
+
+        let mut dispatcher = EventDispatcher::new();
+        dispatcher.add(cache.create_event("invalidate"));
+        drop(dispatcher);
+        assert!(cache.wait_one().is_none());
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn coalescing() {
+        let mut listener = EventListener::<&str>::coalescing();
+        let dirty = listener.create_recurring_event("dirty");
+        let resized = listener.create_recurring_event("resized");
+
+        for _ in 0..100 {
+            dirty.trigger();
+        }
+
+        resized.trigger();
+        dirty.trigger();
+        assert_eq!(listener.wait_some(), ["dirty", "resized"]);
+
+        dirty.trigger();
+        assert_eq!(listener.wait_some(), ["dirty"]);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn next_async() {
+        use crate::thread::block_on;
+
+        let mut listener = EventListener::<usize>::new();
+        let ticks = listener.create_recurring_event(1);
+
+        let handle = thread::spawn(move || {
+            for _ in 0..3 {
+                thread::sleep(Duration::from_millis(5));
+                ticks.trigger();
+            }
+        });
+
+        let total = block_on(async {
+            let mut total = 0;
+
+            while let Some(value) = listener.next_async().await {
+                total += value;
+            }
+
+            total
+        });
+
+        assert_eq!(total, 3);
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn priority() {
+        let mut listener = EventListener::<&str>::new();
+
+        for _ in 0..3 {
+            listener.create_event("data").trigger();
+        }
+
+        listener.create_event_with_priority("pause", 5).trigger();
+        listener.create_event_with_priority("resume", 5).trigger();
+        listener.create_event_with_priority("flush", 1).trigger();
+
+        assert_eq!(
+            listener.wait_all(),
+            ["pause", "resume", "flush", "data", "data", "data"]
+        );
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn bounded() {
+        let mut listener = EventListener::<usize>::bounded(2, OverflowPolicy::DropNewest);
+        (0..4).for_each(|i| listener.create_event(i).trigger());
+        assert_eq!(listener.wait_all(), [0, 1]);
+
+        let mut listener = EventListener::<usize>::bounded(2, OverflowPolicy::DropOldest);
+        (0..4).for_each(|i| listener.create_event(i).trigger());
+        assert_eq!(listener.wait_all(), [2, 3]);
+
+        let mut listener = EventListener::<usize>::bounded(1, OverflowPolicy::Block);
+        let events: Vec<_> = (0..3).map(|i| listener.create_event(i)).collect();
+
+        let handle = thread::spawn(move || {
+            for event in events {
+                event.trigger();
+            }
+        });
+
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(listener.wait_one(), Some(0));
+        assert_eq!(listener.wait_all(), [1, 2]);
+        handle.join().unwrap();
+
+        // dropping the listener releases a trigger blocked on the full queue
+
+        let mut listener = EventListener::<usize>::bounded(1, OverflowPolicy::Block);
+        let events: Vec<_> = (0..2).map(|i| listener.create_event(i)).collect();
+
+        let handle = thread::spawn(move || {
+            for event in events {
+                event.trigger();
+            }
+        });
+
+        thread::sleep(Duration::from_millis(20));
+        drop(listener);
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn revoke() {
+        let mut listener = EventListener::<usize>::new();
+        let fired = listener.create_event(1);
+        let cancelled = listener.create_event(2);
+        assert_eq!(listener.outstanding(), 2);
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            assert!(cancelled.revoke());
+        });
+
+        fired.trigger();
+        assert_eq!(listener.outstanding(), 1);
+        assert_eq!(listener.wait_all(), [1]);
+        assert_eq!(listener.outstanding(), 0);
+        handle.join().unwrap();
+
+        // revoking an event whose listener is gone reports nobody was waiting
+
+        let orphan = listener.create_event(3);
+        let ticks = listener.create_recurring_event(4);
+        drop(listener);
+        assert!(!orphan.revoke());
+        assert!(!ticks.revoke());
+    }
 }
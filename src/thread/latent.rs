@@ -1,15 +1,32 @@
 use crate::thread::AtomicInteger;
+use crate::thread::Channel;
+use crate::thread::StopToken;
+use crate::thread::ThreadPool;
 use crate::thread::{Event, EventListener};
-use std::collections::HashMap;
-use std::sync::{Arc, Condvar, Mutex};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use std::future::{Future, IntoFuture};
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
 
 // ===========================================================================
 // ** LatentWait **
 // ===========================================================================
 
+// lets 'LatentWaiter' wait on latents of different value types together. it's
+// public so callers can build the '&dyn LatentWait' slices it takes.
+
 pub trait LatentWait {
-    fn add_event(&self, event: Event<usize>, listener_id: usize);
-    fn remove_event(&self, listener_id: usize);
+    fn register_waiter(&self, token: usize, event: Event<usize>);
+    fn deregister(&self, token: usize);
     fn is_ready(&self) -> bool;
 }
 
@@ -17,357 +34,1689 @@ pub trait LatentWait {
 // ** Latent **
 // ===========================================================================
 
-// struct LatentData<T: Clone> {
-struct LatentData<T> {
-    value: Mutex<Option<T>>,
-    condvar: Condvar,
-    events: Mutex<HashMap<usize, Event<usize>>>,
+type Deferred = Box<dyn FnOnce() + Send>;
+type SetCallback<T> = Box<dyn FnOnce(&T) -> Deferred + Send>;
+
+// the value slot of a latent. a value that has been moved out with 'take'
+// leaves the latent ready but empty, as does a latent whose setters were all
+// dropped before it was set. a setter dropped while its thread panics
+// poisons the latent instead, and a latent given a ttl with 'expire_after'
+// expires if it isn't set in time. one tied to a 'StopToken' is stopped if
+// the stop is requested first.
+
+enum Slot<T> {
+    Empty,
+    Set(T),
+    Taken,
+    Abandoned,
+    Poisoned,
+    Expired,
+    Stopped,
 }
 
-// impl<T: Clone> LatentData<T> {
-impl<T> LatentData<T> {
-    fn new() -> Self {
-        LatentData {
-            value: Mutex::new(None),
-            condvar: Condvar::new(),
-            events: Mutex::new(HashMap::new()),
-        }
+impl<T> Slot<T> {
+    // -----------------------------------------------------------------------
+
+    fn is_empty(&self) -> bool {
+        matches!(self, Slot::Empty)
     }
-}
 
-#[derive(Clone)]
-pub struct Latent<T: Clone> {
-    shared: Arc<LatentData<T>>,
-}
+    // -----------------------------------------------------------------------
+    // returns 'true' if the latent was resolved without a value before its
+    // producer finished, so a late value should be dropped
+
+    fn is_cut_short(&self) -> bool {
+        matches!(self, Slot::Expired | Slot::Stopped)
+    }
 
-impl<T: Clone> Latent<T> {
     // -----------------------------------------------------------------------
 
-    pub fn new() -> Self {
-        Latent {
-            shared: Arc::new(LatentData::<T>::new()),
+    fn get(&self) -> Option<&T> {
+        match self {
+            Slot::Set(value) => Some(value),
+            _ => None,
         }
     }
 
     // -----------------------------------------------------------------------
+    // returns the set value, or why the latent holds none
 
-    pub fn is_ready(&self) -> bool {
-        let value = self.shared.value.lock().unwrap();
-        value.is_some()
+    fn value(&self) -> Result<&T, LatentError> {
+        match self {
+            Slot::Set(value) => Ok(value),
+            slot => Err(slot.error().unwrap_or(LatentError::Abandoned)),
+        }
     }
 
     // -----------------------------------------------------------------------
+    // like 'value' but moves the value out, leaving the slot 'Taken'. an
+    // error is left in place for the other handles.
 
-    pub fn set(self, value: T) {
-        let mut future_value = self.shared.value.lock().unwrap();
-
-        // latent values can only be set once and the setter's copy is consumed
-        assert!(future_value.is_none(), "value already set");
+    fn take(&mut self) -> Result<T, LatentError> {
+        self.value()?;
 
-        if future_value.is_none() {
-            *future_value = Some(value);
-            self.shared.condvar.notify_all();
+        match std::mem::replace(self, Slot::Taken) {
+            Slot::Set(value) => Ok(value),
+            _ => unreachable!(),
         }
+    }
 
-        let mut events = self.shared.events.lock().unwrap();
-        let entries = events.drain();
+    // -----------------------------------------------------------------------
 
-        for (_, event) in entries {
-            event.trigger();
+    fn error(&self) -> Option<LatentError> {
+        match self {
+            Slot::Taken => Some(LatentError::Taken),
+            Slot::Abandoned => Some(LatentError::Abandoned),
+            Slot::Poisoned => Some(LatentError::Poisoned),
+            Slot::Expired => Some(LatentError::Expired),
+            Slot::Stopped => Some(LatentError::Stopped),
+            _ => None,
         }
     }
+}
 
+impl<T> From<LatentError> for Slot<T> {
     // -----------------------------------------------------------------------
 
-    pub fn wait(self) -> T {
-        let mut value = self.shared.value.lock().unwrap();
-
-        while value.is_none() {
-            value = self.shared.condvar.wait(value).unwrap();
+    fn from(error: LatentError) -> Self {
+        match error {
+            LatentError::Abandoned => Slot::Abandoned,
+            LatentError::Poisoned => Slot::Poisoned,
+            LatentError::Expired => Slot::Expired,
+            LatentError::Stopped => Slot::Stopped,
+            LatentError::Taken => Slot::Taken,
         }
-
-        value.clone().unwrap()
     }
 }
 
-impl<T: Clone> LatentWait for Latent<T> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LatentError {
+    // every 'LatentSetter' was dropped without setting the value
+    Abandoned,
+    // the last 'LatentSetter' was dropped by a panicking thread, such as a
+    // pool task that panicked
+    Poisoned,
+    // the latent wasn't set within the ttl given to 'expire_after'
+    Expired,
+    // the token given to 'stop_on' was stopped before the latent was set
+    Stopped,
+    // the value was moved out by 'take'
+    Taken,
+}
+
+impl LatentError {
     // -----------------------------------------------------------------------
+    // the message a latent's blocking accessors panic with. they release the
+    // value lock first, so the other handles can still read the error.
+
+    fn message(self) -> &'static str {
+        match self {
+            LatentError::Abandoned => "latent abandoned",
+            LatentError::Poisoned => "latent poisoned by a panicking setter",
+            LatentError::Expired => "latent expired",
+            LatentError::Stopped => "latent stopped",
+            LatentError::Taken => "value already taken",
+        }
+    }
+}
 
-    fn add_event(&self, event: Event<usize>, listener_id: usize) {
-        let value = self.shared.value.lock().unwrap();
+static CALLBACK_COUNT: AtomicInteger = AtomicInteger::new(0);
+static LATENT_COUNT: AtomicInteger = AtomicInteger::new(0);
 
-        if value.is_none() {
-            let mut events = self.shared.events.lock().unwrap();
-            events.insert(listener_id, event);
-        } else {
-            event.trigger();
+// the error of the latent whose callbacks this thread is dropping. a setter
+// dropped by one of them resolves its latent with the same error, so an
+// expired or stopped latent passes that on to the latents derived from it.
+
+thread_local! {
+    static CUT_SHORT: Cell<Option<LatentError>> = const { Cell::new(None) };
+}
+
+// ---------------------------------------------------------------------------
+// drop 'callbacks' of a latent resolved with 'error'. the latents derived
+// from a taken one are abandoned, since their value was never set.
+
+fn drop_cut_short<C>(error: Option<LatentError>, callbacks: C) {
+    let error = error.filter(|error| *error != LatentError::Taken);
+    let outer = CUT_SHORT.replace(error);
+    drop(callbacks);
+    CUT_SHORT.set(outer);
+}
+
+// the events waiting on the latent are kept in the shared 'WAITERS' table
+// under its 'id'. 'registered' is set with the value locked when an event is
+// added, so a latent nobody waits on with an event never locks the table.
+
+// struct LatentData<T: Clone> {
+struct LatentData<T> {
+    id: usize,
+    registered: AtomicBool,
+    value: Mutex<Slot<T>>,
+    condvar: Condvar,
+    callbacks: Mutex<Vec<(usize, SetCallback<T>)>>,
+    setters: AtomicInteger,
+    starter: Mutex<Option<Deferred>>,
+    #[cfg(feature = "async")]
+    wakers: Mutex<Vec<Waker>>,
+}
+
+// impl<T: Clone> LatentData<T> {
+impl<T> LatentData<T> {
+    fn new() -> Self {
+        LatentData {
+            id: LATENT_COUNT.increment() as usize,
+            registered: AtomicBool::new(false),
+            value: Mutex::new(Slot::Empty),
+            condvar: Condvar::new(),
+            callbacks: Mutex::new(Vec::new()),
+            setters: AtomicInteger::new(0),
+            starter: Mutex::new(None),
+            #[cfg(feature = "async")]
+            wakers: Mutex::new(Vec::new()),
         }
     }
 
     // -----------------------------------------------------------------------
+    // run the computation of a lazy latent, if it hasn't been started yet
 
-    fn remove_event(&self, listener_id: usize) {
-        let mut events = self.shared.events.lock().unwrap();
-        events.remove(&listener_id);
+    fn start(&self) {
+        let starter = self.starter.lock().unwrap().take();
+
+        if let Some(starter) = starter {
+            starter();
+        }
     }
 
     // -----------------------------------------------------------------------
+    // start a lazy latent & block until the value is set or 'deadline' passes
 
-    fn is_ready(&self) -> bool {
-        self.is_ready()
+    fn wait_until(&self, deadline: Option<Instant>) -> MutexGuard<'_, Slot<T>> {
+        self.start();
+        self.block_until(deadline)
     }
-}
 
-// ===========================================================================
-// ** LatentWaiter **
-// ===========================================================================
-
-pub struct LatentWaiter;
+    // -----------------------------------------------------------------------
+    // like 'wait_until' but doesn't start a lazy latent
 
-static WAITER_COUNT: AtomicInteger = AtomicInteger::new(0);
+    fn block_until(&self, deadline: Option<Instant>) -> MutexGuard<'_, Slot<T>> {
+        let mut value = self.value.lock().unwrap();
 
-impl LatentWaiter {
-    // -----------------------------------------------------------------------
+        while value.is_empty() {
+            value = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
 
-    pub fn wait_one(latents: &Vec<&dyn LatentWait>) -> Option<usize> {
-        let listener_id = WAITER_COUNT.increment();
-        let mut listener = EventListener::<usize>::new();
+                    if now >= deadline {
+                        break;
+                    }
 
-        for (i, latent) in latents.iter().enumerate() {
-            latent.add_event(listener.create_event(i), listener_id as usize);
+                    self.condvar.wait_timeout(value, deadline - now).unwrap().0
+                }
+                None => self.condvar.wait(value).unwrap(),
+            };
         }
 
-        let index = listener.wait_one();
+        value
+    }
+
+    // -----------------------------------------------------------------------
+    // set the value unless the latent is already resolved
 
-        // TODO: need to remove the events added to the latents that didn't fire
+    fn try_set(&self, value: T) -> bool {
+        let future_value = self.value.lock().unwrap();
 
-        for (_, latent) in latents.iter().enumerate() {
-            latent.remove_event(listener_id as usize);
+        if !future_value.is_empty() {
+            return false;
         }
 
-        index
+        self.resolve(future_value, Slot::Set(value));
+        true
     }
 
     // -----------------------------------------------------------------------
+    // store 'slot' & wake everything waiting on the latent. the callbacks copy
+    // what they need while the value is locked, then run without the lock so
+    // they are free to use this latent. a latent resolved without a value
+    // drops its callbacks instead, releasing any setters they hold, which
+    // resolve the latents derived from this one with the same error.
+
+    fn resolve(&self, mut value: MutexGuard<'_, Slot<T>>, slot: Slot<T>) {
+        *value = slot;
+        self.condvar.notify_all();
+
+        let mut callbacks: Vec<_> = self.callbacks.lock().unwrap().drain(..).collect();
+        let deferred: Vec<Deferred> = match value.get() {
+            Some(set_value) => callbacks
+                .drain(..)
+                .map(|(_, callback)| callback(set_value))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        #[cfg(feature = "async")]
+        for waker in self.wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
 
-    pub fn wait_one_v<T: LatentWait>(latents: &Vec<T>) -> Option<usize> {
-        let listener_id = WAITER_COUNT.increment();
-        let mut listener = EventListener::<usize>::new();
+        if self.registered.swap(false, Ordering::Relaxed) {
+            let events = WAITERS.lock().unwrap().take_latent(self.id);
 
-        for (i, latent) in latents.iter().enumerate() {
-            latent.add_event(listener.create_event(i), listener_id as usize);
+            for event in events {
+                event.trigger();
+            }
         }
 
-        let index = listener.wait_one();
+        let error = value.error();
+        drop(value);
+        drop_cut_short(error, callbacks);
 
-        for (_, latent) in latents.iter().enumerate() {
-            latent.remove_event(listener_id as usize);
+        for callback in deferred {
+            callback();
         }
-
-        index
     }
+}
 
+impl<T> Drop for LatentData<T> {
     // -----------------------------------------------------------------------
+    // drop the events still waiting on a latent that was never resolved
 
-    pub fn wait_all(latents: &Vec<&dyn LatentWait>) -> Vec<usize> {
-        let listener_id = WAITER_COUNT.increment();
-        let mut listener = EventListener::<usize>::new();
-
-        for (i, latent) in latents.iter().enumerate() {
-            latent.add_event(listener.create_event(i), listener_id as usize);
+    fn drop(&mut self) {
+        if *self.registered.get_mut() {
+            let events = WAITERS.lock().unwrap().take_latent(self.id);
+            drop(events);
         }
-
-        listener.wait_all()
     }
 }
 
-// ===========================================================================
-// ** LatentGroup **
-// ===========================================================================
-
-pub struct LatentGroup<T: Clone> {
-    latents: HashMap<usize, Latent<T>>,
-    listener: EventListener<usize>,
-    counter: usize,
+pub struct Latent<T> {
+    shared: Arc<LatentData<T>>,
 }
 
-impl<T: Clone> LatentGroup<T> {
+impl<T> Latent<T> {
     // -----------------------------------------------------------------------
 
     pub fn new() -> Self {
-        LatentGroup {
-            latents: HashMap::new(),
-            listener: EventListener::new(),
-            counter: 0,
+        Latent {
+            shared: Arc::new(LatentData::<T>::new()),
         }
     }
 
     // -----------------------------------------------------------------------
+    // create a latent that is already set with 'value'
 
-    pub fn add(&mut self, latent: Latent<T>) {
-        self.counter += 1;
+    pub fn ready(value: T) -> Self {
+        let data = LatentData::<T>::new();
+        *data.value.lock().unwrap() = Slot::Set(value);
 
-        let latent_id = self.counter;
-        let latent_event = self.listener.create_event(latent_id);
-        latent.add_event(latent_event, 0);
-        self.latents.insert(latent_id, latent);
+        Latent {
+            shared: Arc::new(data),
+        }
     }
 
     // -----------------------------------------------------------------------
+    // create a latent set with the result of 'func', which is only put into
+    // 'pool' once the value is first asked for: waited on, polled with
+    // 'is_ready' or 'peek', awaited or used by a combinator. if nothing asks
+    // before the last handle is dropped, 'func' never runs.
+
+    pub fn lazy(pool: &ThreadPool, func: impl FnOnce() -> T + Send + 'static) -> Self
+    where
+        T: Send + 'static,
+    {
+        let latent = Latent::<T>::new();
+        let submitter = pool.default_submitter();
+
+        // the starter only holds a weak handle, so an unused lazy latent
+        // doesn't keep itself alive
+
+        let shared = Arc::downgrade(&latent.shared);
+        let starter: Deferred = Box::new(move || {
+            if let Some(shared) = shared.upgrade() {
+                shared.setters.set(1);
+                let setter = LatentSetter { shared };
+                submitter.put(move || setter.set(func()));
+            }
+        });
 
-    pub fn wait_one(&mut self) -> Option<Latent<T>> {
-        let latent_id_opt = self.listener.wait_one();
+        *latent.shared.starter.lock().unwrap() = Some(starter);
+        latent
+    }
 
-        if let Some(latent_id) = latent_id_opt {
-            return self.latents.remove(&latent_id);
-        }
+    // -----------------------------------------------------------------------
 
-        None
+    pub fn is_ready(&self) -> bool {
+        self.shared.start();
+        let value = self.shared.value.lock().unwrap();
+        !value.is_empty()
     }
 
     // -----------------------------------------------------------------------
+    // create a latent along with the only handle allowed to set it. if every
+    // clone of the setter is dropped first, the latent is abandoned & its
+    // waiters are woken instead of blocking forever.
 
-    pub fn wait_some(&mut self) -> Vec<Latent<T>> {
-        let latent_ids = self.listener.wait_some();
-        let mut latents = Vec::<Latent<T>>::with_capacity(latent_ids.len());
-
-        for latent_id in latent_ids {
-            let latent_opt = self.latents.remove(&latent_id);
+    pub fn pair() -> (LatentSetter<T>, Latent<T>) {
+        let latent = Latent::<T>::new();
+        latent.shared.setters.set(1);
 
-            if let Some(latent) = latent_opt {
-                latents.push(latent);
-            }
-        }
+        let setter = LatentSetter {
+            shared: latent.shared.clone(),
+        };
 
-        latents
+        (setter, latent)
     }
-}
 
-// ===========================================================================
-// ** TESTS **
-// ===========================================================================
+    // -----------------------------------------------------------------------
+    // trigger 'event' once the latent is resolved, or right away if it already
+    // is. this is the hook for executors & multiplexers built outside this
+    // module. 'token' identifies the registration for 'deregister' & should
+    // come from 'LatentWaiter::token' so it can't collide with other waiters
+    // on the same latent.
+
+    pub fn register_waiter(&self, token: usize, event: Event<usize>) {
+        self.shared.start();
+        let value = self.shared.value.lock().unwrap();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::thread;
-    use std::time::Duration;
+        if value.is_empty() {
+            self.shared.registered.store(true, Ordering::Relaxed);
+            let replaced = WAITERS.lock().unwrap().insert(self.shared.id, token, event);
+            drop(replaced);
+        } else {
+            event.trigger();
+        }
+    }
 
     // -----------------------------------------------------------------------
-    // test Latent.wait() & Latent.is_ready()
+    // drop the event registered under 'token' if it hasn't been triggered
 
-    #[test]
-    fn latent_wait() {
-        let latent = Latent::<i32>::new();
-        let latent_clone = latent.clone();
-        let handle = thread::spawn(move || {
-            thread::sleep(Duration::from_millis(100));
-            latent_clone.set(42);
-        });
+    pub fn deregister(&self, token: usize) {
+        let event = WAITERS.lock().unwrap().remove(self.shared.id, token);
+        drop(event);
+    }
 
-        assert_eq!(latent.is_ready(), false);
+    // -----------------------------------------------------------------------
+    // returns 'true' if the latent's setters were dropped before it was set
 
-        let value = latent.wait();
-        handle.join().unwrap();
-        assert_eq!(value, 42);
+    pub fn is_abandoned(&self) -> bool {
+        matches!(*self.shared.value.lock().unwrap(), Slot::Abandoned)
     }
 
     // -----------------------------------------------------------------------
-    // test LatentGroup.wait_one()
+    // returns 'true' if the latent's last setter was dropped by a panic
 
-    #[test]
-    fn latent_group_wait_one() {
-        let latent1 = Latent::<i32>::new();
-        let latent2 = Latent::<i32>::new();
-        let latent3 = Latent::<i32>::new();
+    pub fn is_poisoned(&self) -> bool {
+        matches!(*self.shared.value.lock().unwrap(), Slot::Poisoned)
+    }
 
-        let l1 = latent1.clone();
-        let l2 = latent2.clone();
-        let l3 = latent3.clone();
+    // -----------------------------------------------------------------------
+    // returns 'true' if the latent's ttl ran out before it was set
 
-        // set 'l1' before being added to the wait group
+    pub fn is_expired(&self) -> bool {
+        matches!(*self.shared.value.lock().unwrap(), Slot::Expired)
+    }
 
-        l1.set(42);
+    // -----------------------------------------------------------------------
+    // resolve the latent as expired if it isn't set within 'ttl', waking its
+    // waiters with 'LatentError::Expired'. a value set after that is dropped,
+    // so a slow producer doesn't panic. latents derived from this one expire
+    // with it. the deadline is kept by the shared expiry thread, which only
+    // holds a weak handle to the latent.
+
+    pub fn expire_after(&self, ttl: Duration)
+    where
+        T: Send + 'static,
+    {
+        let shared = Arc::downgrade(&self.shared);
+
+        EXPIRIES.schedule(
+            Instant::now() + ttl,
+            Box::new(move || {
+                if let Some(shared) = shared.upgrade() {
+                    let value = shared.value.lock().unwrap();
+
+                    if value.is_empty() {
+                        shared.resolve(value, Slot::Expired);
+                    }
+                }
+            }),
+        );
+    }
 
-        let mut latent_group = LatentGroup::<i32>::new();
-        assert!(latent_group.wait_one().is_none()); // nothing to wait on should return 'None'
+    // -----------------------------------------------------------------------
+    // returns 'true' if the token given to 'stop_on' stopped the latent
 
-        latent_group.add(latent1);
-        latent_group.add(latent2);
-        latent_group.add(latent3);
+    pub fn is_stopped(&self) -> bool {
+        matches!(*self.shared.value.lock().unwrap(), Slot::Stopped)
+    }
 
-        // wait for 'l1'
+    // -----------------------------------------------------------------------
+    // resolve the latent as stopped when 'token' is stopped, if it hasn't
+    // been set by then. like an expired latent, a value set afterwards is
+    // dropped.
+
+    pub fn stop_on(&self, token: &StopToken)
+    where
+        T: Send + 'static,
+    {
+        token.on_stop_weak(&self.shared, |shared| {
+            let value = shared.value.lock().unwrap();
+
+            if value.is_empty() {
+                shared.resolve(value, Slot::Stopped);
+            }
+        });
+    }
 
-        let opt1 = latent_group.wait_one();
-        assert!(opt1.is_some());
+    // -----------------------------------------------------------------------
 
-        let lat1 = opt1.unwrap();
-        assert!(lat1.is_ready());
+    pub fn set(self, value: T) {
+        let future_value = self.shared.value.lock().unwrap();
 
-        let val1 = lat1.wait();
-        assert!(val1 == 42);
+        if future_value.is_cut_short() {
+            return;
+        }
 
-        // set 'l2' and wait
+        // latent values can only be set once and the setter's copy is consumed
+        assert!(future_value.is_empty(), "value already set");
 
-        l2.set(39);
-        let opt2 = latent_group.wait_one();
-        assert!(opt2.is_some());
+        self.shared.resolve(future_value, Slot::Set(value));
+    }
 
-        let lat2 = opt2.unwrap();
-        assert!(lat2.is_ready());
+    // -----------------------------------------------------------------------
+    // like 'set' but doesn't panic if the latent is already resolved. returns
+    // 'true' if this call set the value, otherwise 'value' is dropped.
 
-        let val2 = lat2.wait();
-        assert!(val2 == 39);
+    pub fn try_set(self, value: T) -> bool {
+        self.shared.try_set(value)
+    }
 
-        // set 'l3' and wait
+    // -----------------------------------------------------------------------
+    // drop a callback registered with 'when_set' that hasn't run yet
 
-        l3.set(27);
-        let opt3 = latent_group.wait_one();
-        assert!(opt3.is_some());
+    fn remove_callback(&self, id: usize) {
+        let mut callbacks = self.shared.callbacks.lock().unwrap();
+        callbacks.retain(|(callback_id, _)| *callback_id != id);
+    }
 
-        let lat3 = opt3.unwrap();
-        assert!(lat3.is_ready());
+    // -----------------------------------------------------------------------
+    // block until the value is set & move it out of the latent, so a result
+    // that is expensive or impossible to clone can be handed to its single
+    // consumer. the other handles stay ready but can no longer read it.
+
+    pub fn take(self) -> T {
+        match self.try_take() {
+            Ok(value) => value,
+            Err(error) => panic!("{}", error.message()),
+        }
+    }
 
-        let val3 = lat3.wait();
-        assert!(val3 == 27);
+    // -----------------------------------------------------------------------
+    // like 'take' but returns an error instead of panicking if the latent
+    // holds no value. a latent that was resolved without one keeps its error
+    // for the other handles.
 
-        assert!(latent_group.wait_one().is_none()); // nothing to wait on should return 'None'
+    pub fn try_take(self) -> Result<T, LatentError> {
+        self.shared.wait_until(None).take()
     }
+}
 
+impl<T: Clone> Latent<T> {
     // -----------------------------------------------------------------------
-    // test LatentGroup.wait_some()
 
-    #[test]
-    fn latent_group_wait_some() {
-        let latent1 = Latent::<i32>::new();
-        let latent2 = Latent::<i32>::new();
-        let latent3 = Latent::<i32>::new();
+    pub fn wait(self) -> T {
+        self.wait_ref()
+    }
 
-        let l1 = latent1.clone();
-        let l2 = latent2.clone();
-        let l3 = latent3.clone();
+    // -----------------------------------------------------------------------
+    // like 'wait_ref' but returns an error instead of panicking if the
+    // latent holds no value, such as when it was abandoned or taken
 
-        // set 'l1' & 'l2' before being added to the wait group
+    pub fn try_wait(&self) -> Result<T, LatentError> {
+        self.shared.wait_until(None).value().cloned()
+    }
 
-        l1.set(42);
-        l2.set(39);
+    // -----------------------------------------------------------------------
+    // like 'wait' but leaves the handle usable so it can be checked again
 
-        let mut latent_group = LatentGroup::<i32>::new();
-        assert!(latent_group.wait_one().is_none()); // nothing to wait on should return 'None'
+    pub fn wait_ref(&self) -> T {
+        match self.try_wait() {
+            Ok(value) => value,
+            Err(error) => panic!("{}", error.message()),
+        }
+    }
 
-        latent_group.add(latent1);
-        latent_group.add(latent2);
-        latent_group.add(latent3);
+    // -----------------------------------------------------------------------
+    // like 'wait_ref' but gives up & returns 'None' if the value isn't set
+    // within 'timeout'. also returns 'None' if the latent resolves without a
+    // value, such as when it is abandoned or expires.
 
-        // wait for 'l1' & 'l2'
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<T> {
+        self.wait_until(Instant::now() + timeout)
+    }
 
-        let mut latents = latent_group.wait_some();
-        assert!(latents.len() == 2);
+    // -----------------------------------------------------------------------
+    // like 'wait_timeout' but against a fixed 'deadline', so several latents
+    // can be waited on in turn within one overall time limit
 
-        let ll1 = latents.pop().unwrap();
-        assert!(ll1.is_ready());
+    pub fn wait_until(&self, deadline: Instant) -> Option<T> {
+        self.shared.wait_until(Some(deadline)).get().cloned()
+    }
 
-        let ll1_value = ll1.wait();
+    // -----------------------------------------------------------------------
+    // returns the value if it has been set, without blocking
+
+    pub fn peek(&self) -> Option<T> {
+        self.shared.start();
+        self.shared.value.lock().unwrap().get().cloned()
+    }
+}
+
+impl<T: Clone + Send + 'static> Latent<T> {
+    // -----------------------------------------------------------------------
+    // run 'callback' with the value once it is set, on the setter's thread. if
+    // the value is already set, 'callback' runs immediately. if the latent is
+    // resolved without a value, such as when it is abandoned, 'callback' is
+    // dropped without running. returns the id for 'remove_callback' while
+    // the callback is pending, or 'None' if it has already run or been
+    // dropped.
+
+    fn when_set(&self, callback: impl FnOnce(T) + Send + 'static) -> Option<usize> {
+        self.shared.start();
+        let value = self.shared.value.lock().unwrap();
+
+        if value.is_empty() {
+            let id = CALLBACK_COUNT.increment() as usize;
+            let callback: SetCallback<T> = Box::new(|value: &T| {
+                let value = value.clone();
+                Box::new(move || callback(value))
+            });
+
+            self.shared.callbacks.lock().unwrap().push((id, callback));
+            return Some(id);
+        }
+
+        let set_value = value.value().cloned();
+        drop(value);
+
+        match set_value {
+            Ok(set_value) => callback(set_value),
+            Err(error) => drop_cut_short(Some(error), callback),
+        }
+
+        None
+    }
+
+    // -----------------------------------------------------------------------
+    // run 'callback' with the value once it is set. the callback runs on the
+    // thread that sets the latent, or right away if it is already set, so it
+    // should be quick. it is dropped without running if the latent is
+    // abandoned.
+
+    pub fn on_ready(&self, callback: impl FnOnce(T) + Send + 'static) {
+        self.when_set(callback);
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'on_ready' but 'callback' is put into 'pool' once the value is set,
+    // for reactions that would hold up the setter
+
+    pub fn on_ready_in(&self, pool: &ThreadPool, callback: impl FnOnce(T) + Send + 'static) {
+        let submitter = pool.default_submitter();
+
+        self.when_set(move |value| {
+            submitter.put(move || callback(value));
+        });
+    }
+
+    // -----------------------------------------------------------------------
+    // put the value into 'channel' once it is set. like 'on_ready' this runs
+    // on the setter's thread, so a full bounded channel holds up the setter.
+    // the value is dropped if 'channel' is closed.
+
+    pub fn pipe_to(&self, channel: &Channel<T>) {
+        let sender = channel.clone();
+
+        self.when_set(move |value| {
+            let _ = sender.put(value);
+        });
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a latent that is set with 'func' applied to this latent's value.
+    // 'func' runs on the thread that sets this latent, so no thread is held
+    // up just to forward the value.
+
+    pub fn map<U: Clone + Send + 'static>(
+        &self,
+        func: impl FnOnce(T) -> U + Send + 'static,
+    ) -> Latent<U> {
+        let (setter, mapped) = Latent::<U>::pair();
+        self.when_set(move |value| setter.set(func(value)));
+        mapped
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a latent that is set with the value of the latent 'func' starts.
+    // 'func' runs on 'pool' once this latent is set, and nothing blocks while
+    // either step is pending.
+
+    pub fn and_then<U: Clone + Send + 'static>(
+        &self,
+        pool: &ThreadPool,
+        func: impl FnOnce(T) -> Latent<U> + Send + 'static,
+    ) -> Latent<U> {
+        let (setter, chained) = Latent::<U>::pair();
+
+        self.on_ready_in(pool, move |value| {
+            func(value).when_set(move |value| setter.set(value));
+        });
+
+        chained
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a latent that is set with both values once 'a' & 'b' are set
+
+    pub fn join<U: Clone + Send + 'static>(a: &Latent<T>, b: &Latent<U>) -> Latent<(T, U)> {
+        let (setter, joined) = Latent::<(T, U)>::pair();
+        let b = b.clone();
+
+        a.when_set(move |a_value| {
+            b.when_set(move |b_value| setter.set((a_value, b_value)));
+        });
+
+        joined
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a latent that is set with all the values, in the order given,
+    // once every latent in 'latents' is set
+
+    pub fn join_all(latents: Vec<Latent<T>>) -> Latent<Vec<T>> {
+        if latents.is_empty() {
+            return Latent::ready(Vec::new());
+        }
+
+        let (setter, joined) = Latent::<Vec<T>>::pair();
+        let pending = Arc::new(Mutex::new((
+            latents.iter().map(|_| None).collect::<Vec<Option<T>>>(),
+            latents.len(),
+            Some(setter),
+        )));
+
+        for (i, latent) in latents.iter().enumerate() {
+            let pending = pending.clone();
+
+            latent.when_set(move |value| {
+                let mut pending = pending.lock().unwrap();
+                let (values, remaining, setter) = &mut *pending;
+                values[i] = Some(value);
+                *remaining -= 1;
+
+                if *remaining == 0 {
+                    let values = values.drain(..).map(Option::unwrap).collect();
+                    let setter = setter.take().unwrap();
+                    drop(pending);
+                    setter.set(values);
+                }
+            });
+        }
+
+        joined
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a latent that is set with the first value to arrive from
+    // 'latents'. once it is set the callbacks left on the other latents are
+    // removed. the returned latent is abandoned if every latent in 'latents'
+    // is, or if 'latents' is empty.
+
+    pub fn race(latents: Vec<Latent<T>>) -> Latent<T> {
+        let (setter, winner) = Latent::<T>::pair();
+        let setter = Arc::new(Mutex::new(Some(setter)));
+        let mut pending = Vec::with_capacity(latents.len());
+
+        for latent in latents {
+            let setter = setter.clone();
+            let id = latent.when_set(move |value| {
+                let setter = setter.lock().unwrap().take();
+
+                if let Some(setter) = setter {
+                    setter.set(value);
+                }
+            });
+
+            // a latent that resolved without a value drops its callback, so
+            // only stop once one of them has set the winner
+
+            match id {
+                Some(id) => pending.push((latent, id)),
+                None if winner.is_ready() => break,
+                None => (),
+            }
+        }
+
+        winner.when_set(move |_| {
+            for (latent, id) in pending {
+                latent.remove_callback(id);
+            }
+        });
+
+        winner
+    }
+}
+
+impl<T, E> Latent<Result<T, E>> {
+    // -----------------------------------------------------------------------
+    // set a fallible latent with the failure 'err', which waiters receive
+    // as an 'Err'
+
+    pub fn fail(self, err: E) {
+        self.set(Err(err));
+    }
+
+    // -----------------------------------------------------------------------
+    // returns 'true' if the latent has been set with an 'Err'
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self.shared.value.lock().unwrap().get(), Some(Err(_)))
+    }
+
+    // -----------------------------------------------------------------------
+    // wait for the latent & return a copy of its error, if it failed. only
+    // the error is cloned, so the success type needn't be 'Clone'. panics
+    // like 'wait_ref' if the latent holds no value.
+
+    pub fn wait_err(&self) -> Option<E>
+    where
+        E: Clone,
+    {
+        let error = self
+            .shared
+            .wait_until(None)
+            .value()
+            .map(|result| result.as_ref().err().cloned());
+
+        match error {
+            Ok(error) => error,
+            Err(error) => panic!("{}", error.message()),
+        }
+    }
+}
+
+// the adapters below derive new latents with 'map', so they run on the
+// setter's thread & never block
+
+impl<T, E> Latent<Result<T, E>>
+where
+    T: Clone + Send + 'static,
+    E: Clone + Send + 'static,
+{
+    // -----------------------------------------------------------------------
+    // returns a latent with the error transformed by 'func'
+
+    pub fn map_err<F: Clone + Send + 'static>(
+        &self,
+        func: impl FnOnce(E) -> F + Send + 'static,
+    ) -> Latent<Result<T, F>> {
+        self.map(|result| result.map_err(func))
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a latent where an error is replaced by the result of 'func',
+    // which can recover with an 'Ok' or fail again
+
+    pub fn or_else<F: Clone + Send + 'static>(
+        &self,
+        func: impl FnOnce(E) -> Result<T, F> + Send + 'static,
+    ) -> Latent<Result<T, F>> {
+        self.map(|result| result.or_else(func))
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a latent with the success value, or 'default' on an error
+
+    pub fn unwrap_or(&self, default: T) -> Latent<T> {
+        self.map(|result| result.unwrap_or(default))
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a latent with the success value, or 'T::default()' on an error
+
+    pub fn unwrap_or_default(&self) -> Latent<T>
+    where
+        T: Default,
+    {
+        self.map(|result| result.unwrap_or_default())
+    }
+}
+
+impl<T> Clone for Latent<T> {
+    // -----------------------------------------------------------------------
+
+    fn clone(&self) -> Self {
+        Latent {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Default for Latent<T> {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        Latent::new()
+    }
+}
+
+impl<T> LatentWait for Latent<T> {
+    // -----------------------------------------------------------------------
+
+    fn register_waiter(&self, token: usize, event: Event<usize>) {
+        Latent::register_waiter(self, token, event);
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn deregister(&self, token: usize) {
+        Latent::deregister(self, token);
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn is_ready(&self) -> bool {
+        Latent::is_ready(self)
+    }
+}
+
+// ===========================================================================
+// ** LatentSetter **
+// ===========================================================================
+
+// the setting half of a latent created with 'Latent::pair'. the latent is
+// abandoned when the last clone is dropped without calling 'set', or
+// poisoned if that happens while the thread is panicking.
+
+pub struct LatentSetter<T> {
+    shared: Arc<LatentData<T>>,
+}
+
+impl<T> LatentSetter<T> {
+    // -----------------------------------------------------------------------
+
+    pub fn set(self, value: T) {
+        let future_value = self.shared.value.lock().unwrap();
+
+        if future_value.is_cut_short() {
+            return;
+        }
+
+        assert!(future_value.is_empty(), "value already set");
+        self.shared.resolve(future_value, Slot::Set(value));
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'Latent::try_set', for producers racing to set the same latent
+
+    pub fn try_set(self, value: T) -> bool {
+        self.shared.try_set(value)
+    }
+}
+
+impl<T> Clone for LatentSetter<T> {
+    // -----------------------------------------------------------------------
+
+    fn clone(&self) -> Self {
+        self.shared.setters.increment();
+
+        LatentSetter {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for LatentSetter<T> {
+    // -----------------------------------------------------------------------
+
+    fn drop(&mut self) {
+        if self.shared.setters.decrement() > 1 {
+            return;
+        }
+
+        let value = self.shared.value.lock().unwrap();
+
+        if value.is_empty() {
+            let slot = match CUT_SHORT.get() {
+                Some(error) => Slot::from(error),
+                None if thread::panicking() => Slot::Poisoned,
+                None => Slot::Abandoned,
+            };
+
+            self.shared.resolve(value, slot);
+        }
+    }
+}
+
+// ===========================================================================
+// ** LatentFuture **
+// ===========================================================================
+
+// a future that resolves with the latent's value once it is set. polling an
+// abandoned or poisoned latent panics, like 'wait'. 'read' clones the value
+// out of the slot, or takes it when the future holds the only handle.
+
+#[cfg(feature = "async")]
+pub struct LatentFuture<T> {
+    latent: Latent<T>,
+    read: fn(&mut Slot<T>) -> Result<T, LatentError>,
+}
+
+#[cfg(feature = "async")]
+impl<T: Clone> LatentFuture<T> {
+    // -----------------------------------------------------------------------
+
+    pub(crate) fn new(latent: Latent<T>) -> Self {
+        LatentFuture {
+            latent,
+            read: |slot| slot.value().cloned(),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> LatentFuture<T> {
+    // -----------------------------------------------------------------------
+    // a future that moves the value out of 'latent', so 'T' needn't be
+    // 'Clone'. only for latents no other handle will read.
+
+    pub(crate) fn taking(latent: Latent<T>) -> Self {
+        LatentFuture {
+            latent,
+            read: Slot::take,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Future for LatentFuture<T> {
+    type Output = T;
+
+    // -----------------------------------------------------------------------
+    // the waker is registered while the value lock is held so a concurrent
+    // 'set' can't slip in between the check & the registration
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        self.latent.shared.start();
+        let mut value = self.latent.shared.value.lock().unwrap();
+
+        if !value.is_empty() {
+            let result = (self.read)(&mut value);
+            drop(value);
+            return Poll::Ready(result.unwrap_or_else(|error| panic!("{}", error.message())));
+        }
+
+        let mut wakers = self.latent.shared.wakers.lock().unwrap();
+
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: Clone> IntoFuture for Latent<T> {
+    type Output = T;
+    type IntoFuture = LatentFuture<T>;
+
+    // -----------------------------------------------------------------------
+    // lets async code 'await' a latent directly
+
+    fn into_future(self) -> LatentFuture<T> {
+        LatentFuture::new(self)
+    }
+}
+
+// ===========================================================================
+// ** ExpiryQueue **
+// ===========================================================================
+
+// the deadlines given to 'expire_after', in order, each with the job that
+// expires its latent. one thread, started by the first deadline, runs the
+// jobs as they fall due, so a ttl costs a queue entry rather than a thread.
+// 'count' tells apart deadlines that fall on the same instant.
+
+static EXPIRIES: ExpiryQueue = ExpiryQueue::new();
+
+struct ExpiryQueue {
+    jobs: Mutex<ExpiryJobs>,
+    condvar: Condvar,
+}
+
+struct ExpiryJobs {
+    deadlines: BTreeMap<(Instant, usize), Deferred>,
+    count: usize,
+    started: bool,
+}
+
+impl ExpiryQueue {
+    // -----------------------------------------------------------------------
+
+    const fn new() -> Self {
+        ExpiryQueue {
+            jobs: Mutex::new(ExpiryJobs {
+                deadlines: BTreeMap::new(),
+                count: 0,
+                started: false,
+            }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // run 'job' on the expiry thread once 'deadline' passes
+
+    fn schedule(&'static self, deadline: Instant, job: Deferred) {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.count += 1;
+        let key = (deadline, jobs.count);
+        jobs.deadlines.insert(key, job);
+
+        if !jobs.started {
+            jobs.started = true;
+            thread::spawn(move || self.run());
+        }
+
+        if jobs
+            .deadlines
+            .first_key_value()
+            .is_some_and(|(first, _)| *first == key)
+        {
+            self.condvar.notify_one();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // the expiry thread's loop. due jobs run without the lock.
+
+    fn run(&self) {
+        let mut jobs = self.jobs.lock().unwrap();
+
+        loop {
+            let now = Instant::now();
+            let mut due = Vec::new();
+
+            while let Some(entry) = jobs.deadlines.first_entry() {
+                if entry.key().0 > now {
+                    break;
+                }
+
+                due.push(entry.remove());
+            }
+
+            if !due.is_empty() {
+                drop(jobs);
+                due.into_iter().for_each(|job| job());
+                jobs = self.jobs.lock().unwrap();
+                continue;
+            }
+
+            jobs = match jobs.deadlines.first_key_value() {
+                Some(((deadline, _), _)) => {
+                    let timeout = deadline.saturating_duration_since(now);
+                    self.condvar.wait_timeout(jobs, timeout).unwrap().0
+                }
+                None => self.condvar.wait(jobs).unwrap(),
+            };
+        }
+    }
+}
+
+// ===========================================================================
+// ** Registration **
+// ===========================================================================
+
+// events added to a set of latents under one token. every wait & group draws
+// its own token, so registrations on a shared latent never collide, and the
+// events that didn't fire are removed when the registration is dropped.
+
+static TOKEN_COUNT: AtomicInteger = AtomicInteger::new(0);
+
+// ===========================================================================
+// ** WaiterTable **
+// ===========================================================================
+
+// the events of every latent in one table, keyed by latent id, with an index
+// of the latents each token is registered on. a latent takes its events out
+// when it is resolved or dropped, & a token's leftover events can be removed
+// in one go. the removed events are returned so they are triggered or dropped
+// after the table is unlocked.
+
+static WAITERS: Mutex<WaiterTable> = Mutex::new(WaiterTable::new());
+
+struct WaiterTable {
+    by_latent: BTreeMap<usize, Vec<(usize, Event<usize>)>>,
+    by_token: BTreeMap<usize, Vec<usize>>,
+}
+
+impl WaiterTable {
+    // -----------------------------------------------------------------------
+
+    const fn new() -> Self {
+        WaiterTable {
+            by_latent: BTreeMap::new(),
+            by_token: BTreeMap::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // add 'event' for 'latent' under 'token', replacing an earlier one
+
+    fn insert(&mut self, latent: usize, token: usize, event: Event<usize>) -> Option<Event<usize>> {
+        let replaced = self.remove(latent, token);
+        self.by_latent
+            .entry(latent)
+            .or_default()
+            .push((token, event));
+        self.by_token.entry(token).or_default().push(latent);
+        replaced
+    }
+
+    // -----------------------------------------------------------------------
+    // remove the event for 'latent' under 'token'
+
+    fn remove(&mut self, latent: usize, token: usize) -> Option<Event<usize>> {
+        let events = self.by_latent.get_mut(&latent)?;
+        let index = events.iter().position(|(t, _)| *t == token)?;
+        let (_, event) = events.swap_remove(index);
+
+        if events.is_empty() {
+            self.by_latent.remove(&latent);
+        }
+
+        Self::unindex(&mut self.by_token, token, latent);
+        Some(event)
+    }
+
+    // -----------------------------------------------------------------------
+    // remove every event waiting on 'latent'
+
+    fn take_latent(&mut self, latent: usize) -> Vec<Event<usize>> {
+        let entries = self.by_latent.remove(&latent).unwrap_or_default();
+
+        entries
+            .into_iter()
+            .map(|(token, event)| {
+                Self::unindex(&mut self.by_token, token, latent);
+                event
+            })
+            .collect()
+    }
+
+    // -----------------------------------------------------------------------
+    // remove every event registered under 'token'
+
+    fn take_token(&mut self, token: usize) -> Vec<Event<usize>> {
+        let latents = self.by_token.remove(&token).unwrap_or_default();
+        let mut taken = Vec::with_capacity(latents.len());
+
+        for latent in latents {
+            let Some(events) = self.by_latent.get_mut(&latent) else {
+                continue;
+            };
+
+            if let Some(index) = events.iter().position(|(t, _)| *t == token) {
+                taken.push(events.swap_remove(index).1);
+            }
+
+            if events.is_empty() {
+                self.by_latent.remove(&latent);
+            }
+        }
+
+        taken
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn unindex(by_token: &mut BTreeMap<usize, Vec<usize>>, token: usize, latent: usize) {
+        if let Some(latents) = by_token.get_mut(&token) {
+            latents.retain(|id| *id != latent);
+
+            if latents.is_empty() {
+                by_token.remove(&token);
+            }
+        }
+    }
+}
+
+struct Registration<'a> {
+    latents: Vec<&'a dyn LatentWait>,
+    token: usize,
+}
+
+impl<'a> Registration<'a> {
+    // -----------------------------------------------------------------------
+    // add an event to each latent that triggers 'listener' with its index
+
+    fn new(
+        latents: impl Iterator<Item = &'a dyn LatentWait>,
+        listener: &mut EventListener<usize>,
+    ) -> Self {
+        let token = LatentWaiter::token();
+        let latents: Vec<_> = latents.collect();
+
+        for (i, latent) in latents.iter().enumerate() {
+            latent.register_waiter(token, listener.create_event(i));
+        }
+
+        Registration { latents, token }
+    }
+}
+
+impl Drop for Registration<'_> {
+    // -----------------------------------------------------------------------
+
+    fn drop(&mut self) {
+        for latent in self.latents.iter() {
+            latent.deregister(self.token);
+        }
+    }
+}
+
+// ===========================================================================
+// ** LatentWaiter **
+// ===========================================================================
+
+pub struct LatentWaiter;
+
+impl LatentWaiter {
+    // -----------------------------------------------------------------------
+    // returns a token for 'Latent::register_waiter' that no other waiter uses
+
+    pub fn token() -> usize {
+        TOKEN_COUNT.increment() as usize
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn wait_one(latents: &[&dyn LatentWait]) -> Option<usize> {
+        let mut listener = EventListener::<usize>::new();
+        let _registration = Registration::new(latents.iter().copied(), &mut listener);
+        listener.wait_one()
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn wait_one_v<T: LatentWait>(latents: &[T]) -> Option<usize> {
+        let mut listener = EventListener::<usize>::new();
+        let latents = latents.iter().map(|latent| latent as &dyn LatentWait);
+        let _registration = Registration::new(latents, &mut listener);
+        listener.wait_one()
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait_one' but returns 'None' if none of the latents is set within
+    // 'timeout'
+
+    pub fn wait_any_timeout(latents: &[&dyn LatentWait], timeout: Duration) -> Option<usize> {
+        let mut listener = EventListener::<usize>::new();
+        let _registration = Registration::new(latents.iter().copied(), &mut listener);
+        listener.wait_one_timeout(timeout)
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn wait_all(latents: &[&dyn LatentWait]) -> Vec<usize> {
+        let mut listener = EventListener::<usize>::new();
+        let _registration = Registration::new(latents.iter().copied(), &mut listener);
+        listener.wait_all()
+    }
+}
+
+// ===========================================================================
+// ** LatentGroup **
+// ===========================================================================
+
+// latents are added with a key, returned along with the latent when it is
+// set, so results can be matched back to the request that produced them.
+// groups built with 'add' use the unit key.
+
+pub struct LatentGroup<T, K = ()> {
+    latents: BTreeMap<usize, (K, Latent<T>)>,
+    listener: EventListener<usize>,
+    counter: usize,
+    token: usize,
+}
+
+impl<T, K> LatentGroup<T, K> {
+    // -----------------------------------------------------------------------
+
+    pub fn new() -> Self {
+        LatentGroup {
+            latents: BTreeMap::new(),
+            listener: EventListener::new(),
+            counter: 0,
+            token: LatentWaiter::token(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the number of latents that haven't been returned by a wait
+
+    pub fn len(&self) -> usize {
+        self.latents.len()
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn is_empty(&self) -> bool {
+        self.latents.is_empty()
+    }
+
+    // -----------------------------------------------------------------------
+    // iterate the latents that haven't been returned by a wait, with their
+    // keys, in the order they were added. some may already be set.
+
+    pub fn pending(&self) -> impl Iterator<Item = (&K, &Latent<T>)> {
+        self.latents.values().map(|(key, latent)| (key, latent))
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn add_keyed(&mut self, key: K, latent: Latent<T>) {
+        self.counter += 1;
+
+        let latent_id = self.counter;
+        let latent_event = self.listener.create_event(latent_id);
+        latent.register_waiter(self.token, latent_event);
+        self.latents.insert(latent_id, (key, latent));
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn wait_one(&mut self) -> Option<Latent<T>> {
+        self.wait_one_keyed().map(|(_, latent)| latent)
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait_one' but also returns the key the latent was added with
+
+    pub fn wait_one_keyed(&mut self) -> Option<(K, Latent<T>)> {
+        let latent_id_opt = self.listener.wait_one();
+
+        if let Some(latent_id) = latent_id_opt {
+            return self.latents.remove(&latent_id);
+        }
+
+        None
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn wait_some(&mut self) -> Vec<Latent<T>> {
+        self.wait_some_keyed()
+            .into_iter()
+            .map(|(_, latent)| latent)
+            .collect()
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait_some' but also returns the keys the latents were added with
+
+    pub fn wait_some_keyed(&mut self) -> Vec<(K, Latent<T>)> {
+        let latent_ids = self.listener.wait_some();
+        self.remove_all(latent_ids)
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait_one' but returns 'None' if no latent is set within 'timeout'
+
+    pub fn wait_one_timeout(&mut self, timeout: Duration) -> Option<Latent<T>> {
+        let latent_id = self.listener.wait_one_timeout(timeout)?;
+        self.latents.remove(&latent_id).map(|(_, latent)| latent)
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait_some' but returns an empty list if no latent is set within
+    // 'timeout'. the latents still in the group are the stragglers.
+
+    pub fn wait_some_timeout(&mut self, timeout: Duration) -> Vec<Latent<T>> {
+        let latent_ids = self.listener.wait_some_timeout(timeout);
+
+        self.remove_all(latent_ids)
+            .into_iter()
+            .map(|(_, latent)| latent)
+            .collect()
+    }
+
+    // -----------------------------------------------------------------------
+    // remove & return the latents with the fired 'latent_ids'
+
+    fn remove_all(&mut self, latent_ids: Vec<usize>) -> Vec<(K, Latent<T>)> {
+        let mut latents = Vec::<(K, Latent<T>)>::with_capacity(latent_ids.len());
+
+        for latent_id in latent_ids {
+            let latent_opt = self.latents.remove(&latent_id);
+
+            if let Some(latent) = latent_opt {
+                latents.push(latent);
+            }
+        }
+
+        latents
+    }
+
+    // -----------------------------------------------------------------------
+    // block until every latent in the group is set & return them all in the
+    // order they were added, leaving the group empty
+
+    pub fn wait_all(&mut self) -> Vec<Latent<T>> {
+        self.wait_all_keyed()
+            .into_iter()
+            .map(|(_, latent)| latent)
+            .collect()
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait_all' but also returns the keys the latents were added with
+
+    pub fn wait_all_keyed(&mut self) -> Vec<(K, Latent<T>)> {
+        let mut remaining = self.latents.len();
+
+        while remaining > 0 {
+            let latent_ids = self.listener.wait_some();
+
+            if latent_ids.is_empty() {
+                break;
+            }
+
+            remaining -= latent_ids.len();
+        }
+
+        std::mem::take(&mut self.latents).into_values().collect()
+    }
+}
+
+impl<T> LatentGroup<T> {
+    // -----------------------------------------------------------------------
+
+    pub fn add(&mut self, latent: Latent<T>) {
+        self.add_keyed((), latent);
+    }
+}
+
+impl<T: Clone + Send + 'static, K> LatentGroup<T, K> {
+    // -----------------------------------------------------------------------
+    // returns a channel that receives the values in the order their latents
+    // are set. once every latent is set or abandoned the channel has no other
+    // handle left, so 'get' reports 'AllSendersDropped' instead of blocking.
+
+    pub fn into_channel(mut self) -> Channel<T> {
+        let output = Channel::new();
+
+        let events = WAITERS.lock().unwrap().take_token(self.token);
+        drop(events);
+
+        for (_, (_, latent)) in std::mem::take(&mut self.latents) {
+            latent.pipe_to(&output);
+        }
+
+        output
+    }
+}
+
+impl<T, K> Default for LatentGroup<T, K> {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        LatentGroup::new()
+    }
+}
+
+impl<T, K> Drop for LatentGroup<T, K> {
+    // -----------------------------------------------------------------------
+    // remove the group's events from the latents that are still pending
+
+    fn drop(&mut self) {
+        let events = WAITERS.lock().unwrap().take_token(self.token);
+        drop(events);
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::{Gate, RecvError, StopSource};
+    use std::thread;
+
+    // -----------------------------------------------------------------------
+    // returns the number of events waiting on 'latent' in the shared table
+
+    fn registered<T>(latent: &Latent<T>) -> usize {
+        let waiters = WAITERS.lock().unwrap();
+        waiters.by_latent.get(&latent.shared.id).map_or(0, Vec::len)
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent.wait() & Latent.is_ready()
+
+    #[test]
+    fn latent_wait() {
+        let latent = Latent::<i32>::new();
+        let latent_clone = latent.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            latent_clone.set(42);
+        });
+
+        assert!(!latent.is_ready());
+
+        let value = latent.wait();
+        handle.join().unwrap();
+        assert_eq!(value, 42);
+    }
+
+    // -----------------------------------------------------------------------
+    // test LatentGroup.wait_one()
+
+    #[test]
+    fn latent_group_wait_one() {
+        let latent1 = Latent::<i32>::new();
+        let latent2 = Latent::<i32>::new();
+        let latent3 = Latent::<i32>::new();
+
+        let l1 = latent1.clone();
+        let l2 = latent2.clone();
+        let l3 = latent3.clone();
+
+        // set 'l1' before being added to the wait group
+
+        l1.set(42);
+
+        let mut latent_group = LatentGroup::<i32>::new();
+        assert!(latent_group.wait_one().is_none()); // nothing to wait on should return 'None'
+
+        latent_group.add(latent1);
+        latent_group.add(latent2);
+        latent_group.add(latent3);
+
+        // wait for 'l1'
+
+        let opt1 = latent_group.wait_one();
+        assert!(opt1.is_some());
+
+        let lat1 = opt1.unwrap();
+        assert!(lat1.is_ready());
+
+        let val1 = lat1.wait();
+        assert!(val1 == 42);
+
+        // set 'l2' and wait
+
+        l2.set(39);
+        let opt2 = latent_group.wait_one();
+        assert!(opt2.is_some());
+
+        let lat2 = opt2.unwrap();
+        assert!(lat2.is_ready());
+
+        let val2 = lat2.wait();
+        assert!(val2 == 39);
+
+        // set 'l3' and wait
+
+        l3.set(27);
+        let opt3 = latent_group.wait_one();
+        assert!(opt3.is_some());
+
+        let lat3 = opt3.unwrap();
+        assert!(lat3.is_ready());
+
+        let val3 = lat3.wait();
+        assert!(val3 == 27);
+
+        assert!(latent_group.wait_one().is_none()); // nothing to wait on should return 'None'
+    }
+
+    // -----------------------------------------------------------------------
+    // test LatentGroup.wait_some()
+
+    #[test]
+    fn latent_group_wait_some() {
+        let latent1 = Latent::<i32>::new();
+        let latent2 = Latent::<i32>::new();
+        let latent3 = Latent::<i32>::new();
+
+        let l1 = latent1.clone();
+        let l2 = latent2.clone();
+        let l3 = latent3.clone();
+
+        // set 'l1' & 'l2' before being added to the wait group
+
+        l1.set(42);
+        l2.set(39);
+
+        let mut latent_group = LatentGroup::<i32>::new();
+        assert!(latent_group.wait_one().is_none()); // nothing to wait on should return 'None'
+
+        latent_group.add(latent1);
+        latent_group.add(latent2);
+        latent_group.add(latent3);
+
+        // wait for 'l1' & 'l2'
+
+        let mut latents = latent_group.wait_some();
+        assert!(latents.len() == 2);
+
+        let ll1 = latents.pop().unwrap();
+        assert!(ll1.is_ready());
+
+        let ll1_value = ll1.wait();
         assert!(ll1_value == 42 || ll1_value == 39);
 
         let ll2 = latents.pop().unwrap();
@@ -387,7 +1736,7 @@ mod tests {
         assert!(ll3.wait() == 27);
 
         let latents = latent_group.wait_some();
-        assert!(latents.len() == 0); // nothing to wait on
+        assert!(latents.is_empty()); // nothing to wait on
     }
 
     // -----------------------------------------------------------------------
@@ -409,13 +1758,26 @@ mod tests {
         let index = LatentWaiter::wait_one(&latents);
         assert!(index.unwrap() == 0);
         assert!(latents.len() == 2);
-        assert!(latent1.is_ready() == true);
-        assert!(latent1.shared.events.lock().unwrap().len() == 0);
+        assert!(latent1.is_ready());
+        assert_eq!(registered(&latent1), 0);
         assert!(latent1.wait() == 42);
         handle.join().unwrap();
 
-        assert!(latent2.is_ready() == false);
-        assert!(latent2.shared.events.lock().unwrap().len() == 0);
+        assert!(!latent2.is_ready());
+        assert_eq!(registered(&latent2), 0);
+
+        // a wait on a latent that is also in a group leaves the group's event
+
+        let mut latent_group = LatentGroup::<String>::new();
+        latent_group.add(latent2.clone());
+        assert_eq!(
+            LatentWaiter::wait_any_timeout(&[&latent2], Duration::from_millis(20)),
+            None
+        );
+        assert_eq!(registered(&latent2), 1);
+
+        drop(latent_group);
+        assert_eq!(registered(&latent2), 0);
 
         /*
         println!("waiting on latent1");
@@ -456,4 +1818,732 @@ mod tests {
         assert!(latent2.wait() == "hello");
         handle.join().unwrap();
     }
+
+    // -----------------------------------------------------------------------
+    // test Latent.peek() & Latent.wait_ref()
+
+    #[test]
+    fn latent_peek() {
+        let latent = Latent::<i32>::new();
+        let setter = latent.clone();
+        assert_eq!(latent.peek(), None);
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            setter.set(7);
+        });
+
+        assert_eq!(latent.wait_ref(), 7);
+        assert_eq!(latent.wait_ref(), 7);
+        assert_eq!(latent.peek(), Some(7));
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent.wait_timeout()
+
+    #[test]
+    fn latent_wait_timeout() {
+        let latent = Latent::<i32>::new();
+        let setter = latent.clone();
+        assert_eq!(latent.wait_timeout(Duration::from_millis(20)), None);
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            setter.set(7);
+        });
+
+        assert_eq!(latent.wait_timeout(Duration::from_secs(5)), Some(7));
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent.wait_until() with one deadline shared by several latents
+
+    #[test]
+    fn latent_wait_until() {
+        let latents: Vec<Latent<i32>> = (0..3).map(|_| Latent::new()).collect();
+        let setters: Vec<Latent<i32>> = latents.iter().take(2).cloned().collect();
+
+        let handle = thread::spawn(move || {
+            for (i, setter) in setters.into_iter().enumerate() {
+                setter.set(i as i32);
+            }
+        });
+
+        let deadline = Instant::now() + Duration::from_millis(100);
+        let values: Vec<Option<i32>> = latents.iter().map(|l| l.wait_until(deadline)).collect();
+        assert_eq!(values, vec![Some(0), Some(1), None]);
+        assert!(Instant::now() >= deadline);
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent.map() before & after the source is set
+
+    #[test]
+    fn latent_map() {
+        let latent = Latent::<i32>::new();
+        let doubled = latent.map(|x| x * 2);
+        let text = doubled.map(|x| format!("{x}"));
+        assert!(!doubled.is_ready());
+
+        latent.clone().set(21);
+        assert_eq!(doubled.peek(), Some(42));
+        assert_eq!(text.wait(), "42");
+        assert_eq!(latent.map(|x| x + 1).peek(), Some(22));
+
+        // a callback on a set latent can use the latent itself
+
+        let source = latent.clone();
+        let sum = latent.map(move |x| x + source.peek().unwrap());
+        assert_eq!(sum.peek(), Some(42));
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent.and_then() chaining pool tasks
+
+    #[test]
+    fn latent_and_then() {
+        let pool = Arc::new(ThreadPool::new(2));
+        let latent = Latent::<i32>::new();
+        let p = pool.clone();
+        let chained = latent
+            .and_then(&pool, move |x| p.put(move || x + 1))
+            .and_then(&pool, |x| Latent::ready(x * 2));
+
+        thread::sleep(Duration::from_millis(20));
+        assert!(!chained.is_ready());
+
+        latent.set(20);
+        assert_eq!(chained.wait_timeout(Duration::from_secs(5)), Some(42));
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent.fail() on a fallible latent
+
+    #[test]
+    fn latent_fail() {
+        let latent = Latent::<Result<i32, String>>::new();
+        let setter = latent.clone();
+        let handle = thread::spawn(move || {
+            setter.fail("connection lost".to_string());
+        });
+
+        assert_eq!(latent.wait_ref(), Err("connection lost".to_string()));
+        assert!(latent.is_failed());
+        handle.join().unwrap();
+
+        let succeeded = Latent::<Result<i32, String>>::new();
+        succeeded.clone().set(Ok(1));
+        assert!(!succeeded.is_failed());
+    }
+
+    // -----------------------------------------------------------------------
+    // test awaiting a Latent set by a pool task
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn latent_await() {
+        use crate::thread::block_on;
+
+        let pool = ThreadPool::new(1);
+        let latent = pool.put(|| {
+            thread::sleep(Duration::from_millis(20));
+            42
+        });
+
+        assert_eq!(block_on(async { latent.await + 1 }), 43);
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent::ready()
+
+    #[test]
+    fn latent_ready() {
+        let latent = Latent::ready("cached".to_string());
+        assert!(latent.is_ready());
+        assert_eq!(latent.map(|s| s.len()).peek(), Some(6));
+        assert_eq!(LatentWaiter::wait_one(&[&latent]), Some(0));
+        assert_eq!(latent.wait(), "cached");
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent.take() with a payload that can't be cloned
+
+    #[test]
+    fn latent_take() {
+        struct Buffer(Vec<u8>);
+
+        let pool = ThreadPool::new(1);
+        let latent = pool.put(|| Buffer(vec![7; 1024]));
+        let reader = latent.clone();
+
+        let buffer = latent.take();
+        assert_eq!(buffer.0.len(), 1024);
+        assert!(reader.is_ready());
+
+        let cached = Latent::ready("cached".to_string());
+        let other = cached.clone();
+        assert_eq!(cached.take(), "cached");
+        assert_eq!(other.peek(), None);
+        assert_eq!(other.try_wait(), Err(LatentError::Taken));
+    }
+
+    // -----------------------------------------------------------------------
+    // test that taking from a latent resolved without a value keeps its error
+
+    #[test]
+    fn latent_try_take() {
+        let (setter, latent) = Latent::<String>::pair();
+        let other = latent.clone();
+        drop(setter);
+
+        assert_eq!(latent.clone().try_take(), Err(LatentError::Abandoned));
+        assert_eq!(other.try_wait(), Err(LatentError::Abandoned));
+
+        let result = thread::spawn(move || latent.take()).join();
+        let message = *result.unwrap_err().downcast::<String>().unwrap();
+        assert_eq!(message, "latent abandoned");
+        assert!(other.is_abandoned());
+
+        let cached = Latent::ready("cached".to_string());
+        let other = cached.clone();
+        assert_eq!(cached.try_take(), Ok("cached".to_string()));
+        assert_eq!(other.try_take(), Err(LatentError::Taken));
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent::join() & Latent::join_all()
+
+    #[test]
+    fn latent_join() {
+        let number = Latent::<i32>::new();
+        let word = Latent::ready("six".to_string());
+        let pair = Latent::join(&number, &word);
+        assert!(!pair.is_ready());
+
+        number.set(6);
+        assert_eq!(pair.wait(), (6, "six".to_string()));
+
+        let pool = ThreadPool::new(4);
+        let latents = (0..8)
+            .map(|i| {
+                pool.put(move || {
+                    thread::sleep(Duration::from_millis(8 - i));
+                    i
+                })
+            })
+            .collect();
+
+        let all = Latent::join_all(latents);
+        assert_eq!(all.wait(), (0..8).collect::<Vec<u64>>());
+        assert_eq!(Latent::<i32>::join_all(Vec::new()).wait(), vec![]);
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent::race()
+
+    #[test]
+    fn latent_race() {
+        let slow = Latent::<i32>::new();
+        let fast = Latent::<i32>::new();
+        let first = Latent::race(vec![slow.clone(), fast.clone()]);
+        assert!(!first.is_ready());
+
+        fast.set(2);
+        assert_eq!(first.wait(), 2);
+        assert!(slow.shared.callbacks.lock().unwrap().is_empty());
+        slow.set(1);
+
+        let ready = Latent::race(vec![Latent::new(), Latent::ready(3)]);
+        assert_eq!(ready.wait(), 3);
+    }
+
+    // -----------------------------------------------------------------------
+    // test that dropping every LatentSetter abandons the latent
+
+    #[test]
+    fn latent_abandoned() {
+        let (setter, latent) = Latent::<i32>::pair();
+        let mapped = latent.map(|x| x + 1);
+        let setter_clone = setter.clone();
+
+        let handle = thread::spawn(move || {
+            drop(setter);
+            thread::sleep(Duration::from_millis(20));
+            drop(setter_clone);
+        });
+
+        assert_eq!(latent.try_wait(), Err(LatentError::Abandoned));
+        assert!(latent.is_ready());
+        assert!(latent.is_abandoned());
+        assert!(mapped.is_abandoned());
+        handle.join().unwrap();
+
+        let (setter, latent) = Latent::pair();
+        setter.set(5);
+        assert_eq!(latent.try_wait(), Ok(5));
+    }
+
+    // -----------------------------------------------------------------------
+    // test that a panicking pool task poisons its latent
+
+    #[test]
+    fn latent_poisoned() {
+        let pool = ThreadPool::new(1);
+        let failed = pool.put(|| -> i32 { panic!("task failure") });
+        let mapped = failed.map(|x| x + 1);
+        assert_eq!(failed.try_wait(), Err(LatentError::Poisoned));
+        assert!(failed.is_poisoned());
+        assert!(!failed.is_abandoned());
+        assert_eq!(mapped.try_wait(), Err(LatentError::Poisoned));
+
+        // the pool keeps running after the panic
+
+        assert_eq!(pool.put(|| 5).try_wait(), Ok(5));
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent.on_ready() & Latent.on_ready_in()
+
+    #[test]
+    fn latent_on_ready() {
+        let latent = Latent::<i32>::new();
+        let total = Arc::new(AtomicInteger::new(0));
+
+        let t = total.clone();
+        latent.on_ready(move |x| {
+            t.add(x);
+        });
+
+        let pool = ThreadPool::new(1);
+        let (done_setter, done) = Latent::pair();
+        let t = total.clone();
+        latent.on_ready_in(&pool, move |x| {
+            t.add(x * 10);
+            done_setter.set(());
+        });
+
+        assert_eq!(total.get(), 0);
+        latent.clone().set(2);
+        done.wait();
+        assert_eq!(total.get(), 22);
+
+        let t = total.clone();
+        latent.on_ready(move |x| {
+            t.add(x);
+        });
+        assert_eq!(total.get(), 24);
+    }
+
+    // -----------------------------------------------------------------------
+    // test LatentGroup.add_keyed() & LatentGroup.wait_one_keyed()
+
+    #[test]
+    fn latent_group_keyed() {
+        let pool = ThreadPool::new(2);
+        let mut latent_group = LatentGroup::<usize, String>::new();
+
+        for url in ["a.com", "bb.com", "ccc.com"] {
+            latent_group.add_keyed(url.to_string(), pool.put(move || url.len()));
+        }
+
+        let mut results = Vec::new();
+
+        while let Some((url, latent)) = latent_group.wait_one_keyed() {
+            assert_eq!(latent.wait(), url.len());
+            results.push(url);
+        }
+
+        results.sort();
+        assert_eq!(results, vec!["a.com", "bb.com", "ccc.com"]);
+    }
+
+    // -----------------------------------------------------------------------
+    // test LatentGroup.len() & LatentGroup.pending()
+
+    #[test]
+    fn latent_group_pending() {
+        let mut latent_group = LatentGroup::<i32, &str>::new();
+        let first = Latent::<i32>::new();
+        assert!(latent_group.is_empty());
+
+        latent_group.add_keyed("first", first.clone());
+        latent_group.add_keyed("second", Latent::new());
+        latent_group.add_keyed("third", Latent::new());
+        assert_eq!(latent_group.len(), 3);
+
+        first.set(1);
+        assert_eq!(latent_group.wait_one_keyed().unwrap().0, "first");
+
+        let keys: Vec<&str> = latent_group.pending().map(|(key, _)| *key).collect();
+        assert_eq!(keys, vec!["second", "third"]);
+        assert!(latent_group.pending().all(|(_, latent)| !latent.is_ready()));
+        assert_eq!(latent_group.len(), 2);
+    }
+
+    // -----------------------------------------------------------------------
+    // test LatentGroup.wait_all()
+
+    #[test]
+    fn latent_group_wait_all() {
+        let pool = ThreadPool::new(3);
+        let mut latent_group = LatentGroup::<u64>::new();
+        assert!(latent_group.wait_all().is_empty());
+
+        for i in 0..3 {
+            latent_group.add(pool.put(move || {
+                thread::sleep(Duration::from_millis(30 - i * 10));
+                i
+            }));
+        }
+
+        let latents = latent_group.wait_all();
+        assert!(latent_group.is_empty());
+        assert!(latents.iter().all(|latent| latent.is_ready()));
+
+        let values: Vec<u64> = latents.into_iter().map(|latent| latent.wait()).collect();
+        assert_eq!(values, vec![0, 1, 2]);
+    }
+
+    // -----------------------------------------------------------------------
+    // test LatentGroup.wait_one_timeout() & LatentGroup.wait_some_timeout()
+
+    #[test]
+    fn latent_group_wait_timeout() {
+        let fast = Latent::<i32>::new();
+        let slow = Latent::<i32>::new();
+        let mut latent_group = LatentGroup::<i32>::new();
+        latent_group.add(fast.clone());
+        latent_group.add(slow.clone());
+
+        let timeout = Duration::from_millis(20);
+        assert!(latent_group.wait_one_timeout(timeout).is_none());
+        assert!(latent_group.wait_some_timeout(timeout).is_empty());
+
+        fast.set(1);
+        assert_eq!(latent_group.wait_some_timeout(timeout).len(), 1);
+        assert!(latent_group.wait_one_timeout(timeout).is_none());
+        assert_eq!(latent_group.len(), 1);
+
+        slow.set(2);
+        assert_eq!(latent_group.wait_one_timeout(timeout).unwrap().wait(), 2);
+        assert!(
+            latent_group
+                .wait_some_timeout(Duration::from_secs(5))
+                .is_empty()
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // test that only one of several racing producers wins Latent.try_set()
+
+    #[test]
+    fn latent_try_set() {
+        let (setter, latent) = Latent::<usize>::pair();
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let setter = setter.clone();
+                thread::spawn(move || setter.try_set(i))
+            })
+            .collect();
+
+        drop(setter);
+        let wins = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .filter(|won| *won)
+            .count();
+
+        assert_eq!(wins, 1);
+        assert!(latent.wait_ref() < 4);
+        assert!(!latent.clone().try_set(9));
+    }
+
+    // -----------------------------------------------------------------------
+    // test the adapters on a fallible latent
+
+    #[test]
+    fn latent_result_adapters() {
+        let latent = Latent::<Result<i32, String>>::new();
+        let code = latent.map_err(|err| err.len());
+        let recovered = latent.or_else(|err| match err.as_str() {
+            "retry" => Ok::<i32, ()>(0),
+            _ => Err(()),
+        });
+        let fallback = latent.unwrap_or(-1);
+        let default = latent.unwrap_or_default();
+
+        latent.clone().fail("retry".to_string());
+        assert_eq!(code.wait(), Err(5));
+        assert_eq!(recovered.wait(), Ok(0));
+        assert_eq!(fallback.wait(), -1);
+        assert_eq!(default.wait(), 0);
+
+        let succeeded = Latent::<Result<i32, String>>::ready(Ok(3));
+        assert_eq!(succeeded.unwrap_or_default().wait(), 3);
+    }
+
+    // -----------------------------------------------------------------------
+    // test that a lazy latent's computation waits for its first consumer
+
+    #[test]
+    fn latent_lazy() {
+        let pool = ThreadPool::new(2);
+        let runs = Arc::new(AtomicInteger::new(0));
+
+        let counter = runs.clone();
+        let latent = Latent::lazy(&pool, move || {
+            counter.increment();
+            5
+        });
+
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(runs.get(), 0);
+        assert_eq!(latent.clone().wait(), 5);
+        assert_eq!(latent.wait(), 5);
+        assert_eq!(runs.get(), 1);
+
+        let counter = runs.clone();
+        let latent = Latent::lazy(&pool, move || {
+            counter.increment();
+            6
+        });
+
+        while !latent.is_ready() {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(latent.peek(), Some(6));
+        assert_eq!(runs.get(), 2);
+
+        let counter = runs.clone();
+        drop(Latent::lazy(&pool, move || counter.increment()));
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(runs.get(), 2);
+    }
+
+    // -----------------------------------------------------------------------
+    // test that a latent with a ttl expires if it isn't set in time
+
+    #[test]
+    fn latent_expired() {
+        let (setter, latent) = Latent::<i32>::pair();
+        let mapped = latent.map(|x| x + 1);
+        latent.expire_after(Duration::from_millis(20));
+        assert_eq!(latent.try_wait(), Err(LatentError::Expired));
+        assert!(latent.is_expired());
+        assert_eq!(mapped.try_wait(), Err(LatentError::Expired));
+
+        // setting the value late is ignored
+
+        setter.set(5);
+        assert!(latent.is_expired());
+        assert_eq!(latent.peek(), None);
+
+        let (setter, latent) = Latent::pair();
+        latent.expire_after(Duration::from_millis(200));
+        setter.set(5);
+        assert_eq!(latent.try_wait(), Ok(5));
+        assert!(!latent.is_expired());
+
+        // many ttls, scheduled out of order, share the expiry thread
+
+        let latents: Vec<_> = (0..1000).map(|_| Latent::<i32>::new()).collect();
+
+        for (i, latent) in latents.iter().enumerate().rev() {
+            latent.expire_after(Duration::from_millis(i as u64 % 50));
+        }
+
+        assert!(
+            latents
+                .iter()
+                .all(|latent| latent.try_wait() == Err(LatentError::Expired))
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent.pipe_to() & LatentGroup.into_channel()
+
+    #[test]
+    fn latent_into_channel() {
+        let (setters, latents): (Vec<_>, Vec<_>) = (0..3).map(|_| Latent::<i32>::pair()).unzip();
+        let mut group = LatentGroup::new();
+
+        for latent in latents {
+            group.add(latent);
+        }
+
+        let channel = group.into_channel();
+        let mut setters = setters.into_iter();
+        let first = setters.next().unwrap();
+
+        let handle = thread::spawn(move || {
+            for (i, setter) in setters.rev().enumerate() {
+                setter.set(i as i32 + 1);
+                thread::sleep(Duration::from_millis(10));
+            }
+        });
+
+        assert_eq!(channel.get(), Ok(1));
+        assert_eq!(channel.get(), Ok(2));
+        handle.join().unwrap();
+
+        drop(first);
+        assert_eq!(channel.get(), Err(RecvError::AllSendersDropped));
+
+        let output = Channel::new();
+        Latent::ready(5).pipe_to(&output);
+        assert_eq!(output.get(), Ok(5));
+    }
+
+    // -----------------------------------------------------------------------
+    // test Latent.register_waiter() & Latent.deregister()
+
+    #[test]
+    fn latent_register_waiter() {
+        let (setter, latent) = Latent::<i32>::pair();
+        let mut listener = EventListener::<usize>::new();
+        let token = LatentWaiter::token();
+        assert_ne!(token, LatentWaiter::token());
+
+        latent.register_waiter(token, listener.create_event(7));
+        setter.set(5);
+        assert_eq!(listener.wait_one(), Some(7));
+
+        // a resolved latent triggers the event straight away
+
+        latent.register_waiter(token, listener.create_event(8));
+        assert_eq!(listener.wait_one(), Some(8));
+
+        // a deregistered event is never triggered
+
+        let (setter, latent) = Latent::<i32>::pair();
+        latent.register_waiter(token, listener.create_event(9));
+        latent.deregister(token);
+        setter.set(5);
+        assert!(
+            listener
+                .wait_one_timeout(Duration::from_millis(20))
+                .is_none()
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // test that concurrent waits on shared latents each get their own token &
+    // leave no events behind on the latents that didn't win
+
+    #[test]
+    fn latent_waiter_tokens() {
+        let (setter, winner) = Latent::<i32>::pair();
+        let loser = Latent::<i32>::new();
+        let mut group = LatentGroup::new();
+        group.add(loser.clone());
+
+        thread::scope(|scope| {
+            let waits: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| LatentWaiter::wait_one(&[&loser, &winner])))
+                .collect();
+
+            while registered(&loser) < 5 {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            setter.set(5);
+
+            for wait in waits {
+                assert_eq!(wait.join().unwrap(), Some(1));
+            }
+        });
+
+        // only the group's registration is left on the losing latent
+
+        let token = group.token;
+        assert_eq!(registered(&loser), 1);
+        drop(group);
+        assert_eq!(registered(&loser), 0);
+        assert!(!WAITERS.lock().unwrap().by_token.contains_key(&token));
+
+        // dropping a pending latent drops its events, ending the wait
+
+        let mut group = LatentGroup::new();
+        let id = loser.shared.id;
+        group.add(loser);
+        assert!(WAITERS.lock().unwrap().by_latent.contains_key(&id));
+
+        let dropped = group.latents.pop_first().unwrap();
+        drop(dropped);
+        assert!(!WAITERS.lock().unwrap().by_latent.contains_key(&id));
+        assert!(group.wait_one().is_none());
+
+        // a latent nobody registers with is resolved without the table
+
+        let latent = Latent::new();
+        latent.clone().set(1);
+        assert!(!latent.shared.registered.load(Ordering::Relaxed));
+    }
+
+    // -----------------------------------------------------------------------
+    // test the combinators & timed waits on latents resolved without a value
+
+    #[test]
+    fn latent_unresolved_combinators() {
+        let (setter, abandoned) = Latent::<i32>::pair();
+        drop(setter);
+
+        let expired = Latent::<i32>::new();
+        expired.expire_after(Duration::from_millis(1));
+        assert_eq!(expired.try_wait(), Err(LatentError::Expired));
+
+        let source = StopSource::new();
+        let stopped = Latent::<i32>::new();
+        stopped.stop_on(&source.token());
+        source.stop();
+
+        let channel = Channel::<i32>::new();
+        let ran = Arc::new(AtomicInteger::new(0));
+
+        for latent in [&abandoned, &expired, &stopped] {
+            let timeout = Duration::from_secs(5);
+            let error = latent.try_wait().unwrap_err();
+            assert_eq!(latent.wait_timeout(timeout), None);
+            assert_eq!(latent.map(|x| x + 1).try_wait(), Err(error));
+
+            let joined = Latent::join(latent, &Latent::ready(1));
+            assert_eq!(joined.try_wait(), Err(error));
+
+            let ran = ran.clone();
+            latent.on_ready(move |_| {
+                ran.increment();
+            });
+
+            latent.pipe_to(&channel);
+
+            // the inputs resolved without a value don't end the race
+
+            let (setter, pending) = Latent::pair();
+            let winner = Latent::race(vec![latent.clone(), pending]);
+            setter.set(7);
+            assert_eq!(winner.try_wait(), Ok(7));
+        }
+
+        assert_eq!(ran.get(), 0);
+        assert!(channel.is_empty());
+
+        // a pool task removed by 'drain' abandons its latent
+
+        let pool = ThreadPool::new(1);
+        let gate = Gate::arc();
+        let g = gate.clone();
+        pool.put(move || g.wait());
+
+        let queued = pool.put(|| 1);
+        drop(pool.drain());
+        assert_eq!(
+            queued.map(|x| x + 1).try_wait(),
+            Err(LatentError::Abandoned)
+        );
+        gate.open();
+    }
 }
@@ -1,13 +1,82 @@
 mod atomic;
+mod barrier;
 mod channel;
 mod event;
 mod latent;
+mod monitor;
+mod notify;
+mod once;
+mod pipeline;
 mod pool;
+mod registry;
+mod routed;
+mod select;
+mod semaphore;
 mod signal;
+mod spin;
+mod stop;
+mod ticker;
+mod watch;
 
 pub use atomic::AtomicInteger;
-pub use channel::Channel;
-pub use event::{Event, EventListener};
-pub use latent::{Latent, LatentGroup, LatentWaiter};
-pub use pool::ThreadPool;
+pub use barrier::Barrier;
+#[cfg(feature = "async")]
+pub use channel::RecvFuture;
+pub use channel::{Channel, ChannelStats, OverflowPolicy, ProducerId, RecvError, SendError};
+#[cfg(feature = "async")]
+pub use event::NextEvent;
+pub use event::{Event, EventDispatcher, EventListener, RecurringEvent};
+#[cfg(feature = "async")]
+pub use latent::LatentFuture;
+pub use latent::{Latent, LatentError, LatentGroup, LatentSetter, LatentWait, LatentWaiter};
+pub use monitor::Monitor;
+pub use notify::Notify;
+pub use once::OnceValue;
+pub use pipeline::Pipeline;
+pub use pool::{
+    CancelFlag, PoolState, PoolStats, RejectionPolicy, Submitter, Task, TaskId, ThreadPool,
+    ThreadPoolBuilder,
+};
+pub use registry::{Registration, ThreadRegistry};
+pub use routed::RoutedChannel;
+pub use select::{Select, SelectArms};
+pub use semaphore::{Permit, Semaphore};
 pub use signal::{Gate, Signal};
+pub use spin::SpinLock;
+pub use stop::{StopSource, StopToken};
+pub use ticker::Ticker;
+pub use watch::Watch;
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+// poll 'future' on this thread, parking between wakeups. shared by the tests
+// of the async features.
+
+#[cfg(all(test, feature = "async"))]
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread;
+
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+
+    loop {
+        if let Poll::Ready(value) = future.as_mut().poll(&mut cx) {
+            return value;
+        }
+
+        thread::park();
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+// ===========================================================================
+// ** Monitor **
+// ===========================================================================
+
+// a value behind a mutex with a condvar for waiting on it. every change made
+// through 'update_and_notify' wakes the waiters, which recheck their
+// predicates, so callers don't have to write their own condvar loops.
+
+pub struct Monitor<T> {
+    state: Mutex<T>,
+    condvar: Condvar,
+}
+
+impl<T> Monitor<T> {
+    // -----------------------------------------------------------------------
+
+    pub fn new(value: T) -> Self {
+        Monitor {
+            state: Mutex::new(value),
+            condvar: Condvar::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // lock the value to read it. changes made through the guard don't wake
+    // the waiters.
+
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        self.state.lock().unwrap()
+    }
+
+    // -----------------------------------------------------------------------
+    // change the value with 'func' & wake the waiters. returns what 'func'
+    // returns.
+
+    pub fn update_and_notify<R>(&self, func: impl FnOnce(&mut T) -> R) -> R {
+        let mut state = self.state.lock().unwrap();
+        let result = func(&mut state);
+        self.condvar.notify_all();
+        result
+    }
+
+    // -----------------------------------------------------------------------
+    // block until 'predicate' holds for the value & return it still locked
+
+    pub fn wait_until(&self, mut predicate: impl FnMut(&T) -> bool) -> MutexGuard<'_, T> {
+        let state = self.state.lock().unwrap();
+        self.condvar
+            .wait_while(state, |state| !predicate(state))
+            .unwrap()
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait_until' but returns 'None' if 'predicate' doesn't hold within
+    // 'timeout'
+
+    pub fn wait_until_timeout(
+        &self,
+        timeout: Duration,
+        mut predicate: impl FnMut(&T) -> bool,
+    ) -> Option<MutexGuard<'_, T>> {
+        let state = self.state.lock().unwrap();
+        let (state, result) = self
+            .condvar
+            .wait_timeout_while(state, timeout, |state| !predicate(state))
+            .unwrap();
+
+        if result.timed_out() {
+            return None;
+        }
+
+        Some(state)
+    }
+}
+
+impl<T: Default> Default for Monitor<T> {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        Monitor::new(T::default())
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_monitor() {
+        let queue = Monitor::new(Vec::<i32>::new());
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for i in 1..=3 {
+                    thread::sleep(Duration::from_millis(5));
+                    queue.update_and_notify(|items| items.push(i));
+                }
+            });
+
+            let items = queue.wait_until(|items| items.len() == 3);
+            assert_eq!(*items, [1, 2, 3]);
+        });
+
+        let timeout = Duration::from_millis(20);
+        assert!(
+            queue
+                .wait_until_timeout(timeout, |items| items.is_empty())
+                .is_none()
+        );
+        assert_eq!(queue.update_and_notify(|items| items.pop()), Some(3));
+        assert_eq!(queue.lock().len(), 2);
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+// ===========================================================================
+// ** Notify **
+// ===========================================================================
+
+// wakes a single waiter, storing at most one permit when nobody is waiting.
+// any number of 'notify_one' calls before a 'wait' collapse into one wakeup,
+// which suits a worker loop that only needs to know there is new work,
+// unlike 'Signal', which banks a permit for every signal.
+
+pub struct Notify {
+    permit: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl Notify {
+    // -----------------------------------------------------------------------
+
+    pub fn new() -> Self {
+        Notify {
+            permit: Mutex::new(false),
+            condvar: Condvar::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn arc() -> Arc<Self> {
+        Arc::new(Notify::new())
+    }
+
+    // -----------------------------------------------------------------------
+    // store the permit, if it isn't already, & wake one waiter to take it
+
+    pub fn notify_one(&self) {
+        *self.permit.lock().unwrap() = true;
+        self.condvar.notify_one();
+    }
+
+    // -----------------------------------------------------------------------
+    // block until the permit is stored & consume it
+
+    pub fn wait(&self) {
+        let permit = self.permit.lock().unwrap();
+        let mut permit = self.condvar.wait_while(permit, |permit| !*permit).unwrap();
+        *permit = false;
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait' but returns 'false' if not notified within 'timeout'
+
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let permit = self.permit.lock().unwrap();
+        let (mut permit, _) = self
+            .condvar
+            .wait_timeout_while(permit, timeout, |permit| !*permit)
+            .unwrap();
+
+        let notified = *permit;
+        *permit = false;
+        notified
+    }
+}
+
+impl Default for Notify {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        Notify::new()
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_notify() {
+        let notify = Notify::arc();
+        let timeout = Duration::from_millis(20);
+
+        // repeated notifications store a single permit
+
+        notify.notify_one();
+        notify.notify_one();
+        assert!(notify.wait_timeout(timeout));
+        assert!(!notify.wait_timeout(timeout));
+
+        let waker = notify.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            waker.notify_one();
+        });
+
+        notify.wait();
+        handle.join().unwrap();
+        assert!(!notify.wait_timeout(timeout));
+    }
+}
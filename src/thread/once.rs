@@ -0,0 +1,166 @@
+use std::sync::{Condvar, Mutex};
+
+// ===========================================================================
+// ** OnceValue **
+// ===========================================================================
+
+// a value initialized by whichever caller gets to it first. the other callers
+// block while it runs & then receive a clone of the result. if the
+// initializer panics, the value is left empty for the next caller to retry.
+
+enum OnceState<T> {
+    Empty,
+    Running,
+    Ready(T),
+}
+
+pub struct OnceValue<T> {
+    state: Mutex<OnceState<T>>,
+    condvar: Condvar,
+}
+
+impl<T> OnceValue<T> {
+    // -----------------------------------------------------------------------
+
+    pub fn new() -> Self {
+        OnceValue {
+            state: Mutex::new(OnceState::Empty),
+            condvar: Condvar::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn is_ready(&self) -> bool {
+        matches!(*self.state.lock().unwrap(), OnceState::Ready(_))
+    }
+}
+
+impl<T: Clone> OnceValue<T> {
+    // -----------------------------------------------------------------------
+    // returns the value if it has been initialized, without blocking
+
+    pub fn get(&self) -> Option<T> {
+        match &*self.state.lock().unwrap() {
+            OnceState::Ready(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the value, running 'init' on this thread if no other caller has
+    // initialized it yet, or waiting for the caller that is
+
+    pub fn get_or_init(&self, init: impl FnOnce() -> T) -> T {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            match &*state {
+                OnceState::Ready(value) => return value.clone(),
+                OnceState::Running => state = self.condvar.wait(state).unwrap(),
+                OnceState::Empty => break,
+            }
+        }
+
+        *state = OnceState::Running;
+        drop(state);
+
+        let reset = ResetOnPanic { once: self };
+        let value = init();
+        std::mem::forget(reset);
+
+        let mut state = self.state.lock().unwrap();
+        *state = OnceState::Ready(value.clone());
+        self.condvar.notify_all();
+        value
+    }
+}
+
+impl<T> Default for OnceValue<T> {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        OnceValue::new()
+    }
+}
+
+// ===========================================================================
+// ** ResetOnPanic **
+// ===========================================================================
+
+// empties the value again if the initializer unwinds, waking a waiter to
+// take over
+
+struct ResetOnPanic<'a, T> {
+    once: &'a OnceValue<T>,
+}
+
+impl<T> Drop for ResetOnPanic<'_, T> {
+    // -----------------------------------------------------------------------
+
+    fn drop(&mut self) {
+        *self.once.state.lock().unwrap() = OnceState::Empty;
+        self.once.condvar.notify_all();
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::{AtomicInteger, ThreadPool};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_once_value() {
+        let pool = ThreadPool::new(4);
+        let config = Arc::new(OnceValue::<String>::new());
+        let runs = Arc::new(AtomicInteger::new(0));
+
+        let values: Vec<_> = (0..4)
+            .map(|_| {
+                let config = config.clone();
+                let runs = runs.clone();
+
+                pool.put(move || {
+                    config.get_or_init(|| {
+                        runs.increment();
+                        thread::sleep(Duration::from_millis(20));
+                        "loaded".to_string()
+                    })
+                })
+            })
+            .collect();
+
+        for value in values {
+            assert_eq!(value.wait(), "loaded");
+        }
+
+        assert_eq!(runs.get(), 1);
+        assert_eq!(config.get().as_deref(), Some("loaded"));
+    }
+
+    // -----------------------------------------------------------------------
+    // a panicking initializer leaves the value for the next caller
+
+    #[test]
+    fn validate_once_value_panic() {
+        let once = OnceValue::<i32>::new();
+
+        thread::scope(|scope| {
+            let failed = scope.spawn(|| once.get_or_init(|| panic!("init failed")));
+            assert!(failed.join().is_err());
+        });
+
+        assert!(!once.is_ready());
+        assert_eq!(once.get_or_init(|| 5), 5);
+        assert_eq!(once.get_or_init(|| 6), 5);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::thread::Channel;
+use crate::thread::Latent;
+use crate::thread::ThreadPool;
+use std::sync::Arc;
+
+// ===========================================================================
+// ** Pipeline **
+// ===========================================================================
+
+// a chain of stages connected by channels. each worker of a stage runs on a
+// dedicated pool thread rather than a pool worker, since it blocks on its
+// input for as long as the pipeline runs, and a queued worker would hold its
+// input open without reading it. the pool joins the dedicated threads when
+// it's dropped. the workers read from the previous stage's channel and write
+// to the next, & exit when their input ends, which in turn ends the following
+// stage.
+
+pub struct Pipeline<'a, T> {
+    pool: &'a ThreadPool,
+    output: Channel<T>,
+    workers: Vec<Latent<()>>,
+}
+
+impl<'a, T: Send + 'static> Pipeline<'a, T> {
+    // -----------------------------------------------------------------------
+    // start a pipeline that reads the items put into 'input'
+
+    pub fn new(pool: &'a ThreadPool, input: &Channel<T>) -> Self {
+        Pipeline {
+            pool,
+            output: input.clone(),
+            workers: Vec::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // add a stage of 'workers' threads that each apply 'func' to the items
+    // coming out of the previous stage
+
+    pub fn stage<U: Send + 'static>(
+        mut self,
+        workers: usize,
+        func: impl Fn(T) -> U + Send + Sync + 'static,
+    ) -> Pipeline<'a, U> {
+        let output = Channel::<U>::new();
+        let func = Arc::new(func);
+
+        for _ in 0..workers {
+            let input = self.output.clone();
+            let output = output.clone();
+            let func = func.clone();
+
+            self.workers.push(self.pool.spawn_dedicated(move || {
+                while let Ok(item) = input.get() {
+                    if output.put(func(item)).is_err() {
+                        break;
+                    }
+                }
+            }));
+        }
+
+        Pipeline {
+            pool: self.pool,
+            output,
+            workers: self.workers,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the channel the last stage writes to
+
+    pub fn output(self) -> Channel<T> {
+        self.output
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the channel the last stage writes to, & a latent that is set
+    // once every stage worker has exited. it's poisoned if a worker panics.
+
+    pub fn finish(self) -> (Channel<T>, Latent<Vec<()>>) {
+        (self.output, Latent::join_all(self.workers))
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::LatentError;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_pipeline() {
+        let pool = ThreadPool::new(1);
+        let input = Channel::<i32>::new();
+        let output = Pipeline::new(&pool, &input)
+            .stage(2, |x| x + 1)
+            .stage(1, |x| x * 2)
+            .output();
+
+        for i in 1..=10 {
+            input.put(i).unwrap();
+        }
+
+        drop(input);
+        let mut sum = 0;
+
+        while let Ok(item) = output.get() {
+            sum += item;
+        }
+
+        assert_eq!(sum, 130);
+    }
+
+    // -----------------------------------------------------------------------
+    // test that 'finish' reports when the workers exit, & a panicking stage
+
+    #[test]
+    fn validate_pipeline_finish() {
+        let pool = ThreadPool::new(1);
+        let input = Channel::<i32>::new();
+        let (output, done) = Pipeline::new(&pool, &input).stage(2, |x| x + 1).finish();
+
+        input.put_many(1..=4).unwrap();
+        drop(input);
+
+        let mut items: Vec<i32> = std::iter::from_fn(|| output.get().ok()).collect();
+        items.sort();
+        assert_eq!(items, vec![2, 3, 4, 5]);
+        assert_eq!(done.wait().len(), 2);
+
+        let input = Channel::<i32>::new();
+        let (_output, done) = Pipeline::new(&pool, &input)
+            .stage(1, |x| if x < 0 { panic!("bad item") } else { x })
+            .finish();
+
+        input.put(-1).unwrap();
+        drop(input);
+        assert_eq!(done.try_wait(), Err(LatentError::Poisoned));
+    }
+}
@@ -1,154 +1,1225 @@
 use crate::thread::AtomicInteger;
 use crate::thread::Channel;
+use crate::thread::Gate;
 use crate::thread::Latent;
-use crate::thread::Signal;
-use std::sync::Arc;
+#[cfg(feature = "async")]
+use crate::thread::LatentFuture;
+use crate::thread::StopToken;
+use std::any::Any;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
 // ===========================================================================
-struct Task {
-    func: Box<dyn FnOnce() + Send + 'static>,
+// ** Task **
+// ===========================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(pub u64);
+
+// what a task does once the pool has finished its bookkeeping for it, such as
+// setting its latent. running it last means a caller woken by the latent
+// sees the task's worker as free again.
+
+type Completion = Box<dyn FnOnce() + Send>;
+
+pub struct Task {
+    id: TaskId,
+    tag: Option<String>,
+    func: Box<dyn FnOnce() -> Completion + Send + 'static>,
 }
 
 impl Task {
-    fn new(func: impl FnOnce() + Send + 'static) -> Self {
+    // -----------------------------------------------------------------------
+    // the id is assigned when the task is submitted to the pool
+
+    fn new(func: impl FnOnce() -> Completion + Send + 'static) -> Self {
         Task {
+            id: TaskId(0),
+            tag: None,
             func: Box::new(func),
         }
     }
+
+    // -----------------------------------------------------------------------
+    // wrap 'func' in a task that sets the returned latent with its result
+
+    fn with_latent<T: Send + 'static>(
+        func: impl FnOnce() -> T + Send + 'static,
+    ) -> (Self, Latent<T>) {
+        let (setter, latent) = Latent::<T>::pair();
+        let task = Task::new(move || {
+            let r = func();
+            Box::new(move || setter.set(r))
+        });
+
+        (task, latent)
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    // -----------------------------------------------------------------------
+    // run the task on the calling thread
+
+    pub fn run(self) {
+        let complete = self.execute();
+        complete();
+    }
+
+    // -----------------------------------------------------------------------
+    // run the task's work, returning its completion for the worker to call
+    // after releasing the task's running slot
+
+    fn execute(self) -> Completion {
+        (self.func)()
+    }
+}
+
+// ===========================================================================
+// ** CancelFlag **
+// ===========================================================================
+
+// passed to tagged tasks so they can stop early once their tag is cancelled
+
+#[derive(Clone)]
+pub struct CancelFlag {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelFlag {
+    // -----------------------------------------------------------------------
+
+    fn new() -> Self {
+        CancelFlag {
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn cancel(&self) {
+        self.cancelled.store(true, AtomicOrdering::Release);
+    }
+
+    // -----------------------------------------------------------------------
+    // returns 'true' while a task still holds a clone of the flag
+
+    fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.cancelled) > 1
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(AtomicOrdering::Acquire)
+    }
+}
+
+// ===========================================================================
+// ** RejectionPolicy **
+// ===========================================================================
+
+// what 'put' does when a bounded pool's queue is full. tasks that are dropped
+// never run, so their latents are never set.
+
+#[derive(Clone)]
+pub enum RejectionPolicy {
+    Block,
+    DropNewest,
+    DropOldest,
+    Handler(Arc<dyn Fn(Task) + Send + Sync>),
+}
+
+// ===========================================================================
+// ** PoolState **
+// ===========================================================================
+
+// a consistent view of the pool, taken under a single lock of its queue
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolState {
+    pub queued: usize,
+    pub running: usize,
+    pub idle: usize,
+}
+
+// ===========================================================================
+// ** PoolStats **
+// ===========================================================================
+
+#[derive(Debug, Clone, Copy)]
+pub struct PoolStats {
+    pub restarts: usize,
+    pub rejected: usize,
+}
+
+// ===========================================================================
+// ** TokenBucket **
+// ===========================================================================
+
+// holds up to 'rate' tokens and refills at 'rate' tokens per second
+
+struct TokenBucket {
+    rate: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    // -----------------------------------------------------------------------
+
+    fn new(rate: u32) -> Self {
+        let rate = rate as f64;
+
+        TokenBucket {
+            rate,
+            state: Mutex::new((rate, Instant::now())),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // take a token, sleeping until one is available
+
+    fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let (tokens, last) = &mut *state;
+                let now = Instant::now();
+                *tokens =
+                    (*tokens + now.duration_since(*last).as_secs_f64() * self.rate).min(self.rate);
+                *last = now;
+
+                if *tokens >= 1.0 {
+                    *tokens -= 1.0;
+                    return;
+                }
+
+                Duration::from_secs_f64((1.0 - *tokens) / self.rate)
+            };
+
+            thread::sleep(wait);
+        }
+    }
 }
 
+// ===========================================================================
+// ** TaskQueue **
+// ===========================================================================
+
+// the queued tasks, kept in one lane per submitter. 'pop' takes from each lane
+// in turn, so with a single lane the queue is a plain fifo.
+
+struct TaskQueue {
+    lanes: VecDeque<(u64, VecDeque<Task>)>,
+    len: usize,
+}
+
+impl TaskQueue {
+    // -----------------------------------------------------------------------
+
+    fn new() -> Self {
+        TaskQueue {
+            lanes: VecDeque::new(),
+            len: 0,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn push(&mut self, lane: u64, task: Task) {
+        match self.lanes.iter_mut().find(|(id, _)| *id == lane) {
+            Some((_, tasks)) => tasks.push_back(task),
+            None => self.lanes.push_back((lane, VecDeque::from([task]))),
+        }
+
+        self.len += 1;
+    }
+
+    // -----------------------------------------------------------------------
+    // take the next task from the front lane & move that lane to the back
+
+    fn pop(&mut self) -> Option<Task> {
+        let (lane, mut tasks) = self.lanes.pop_front()?;
+        let task = tasks.pop_front();
+
+        if !tasks.is_empty() {
+            self.lanes.push_back((lane, tasks));
+        }
+
+        self.len -= 1;
+        task
+    }
+
+    // -----------------------------------------------------------------------
+    // take the task that was submitted first, whichever lane it is in
+
+    fn pop_oldest(&mut self) -> Option<Task> {
+        let index = (0..self.lanes.len()).min_by_key(|&i| self.lanes[i].1.front().map(Task::id))?;
+        let task = self.lanes[index].1.pop_front();
+
+        if self.lanes[index].1.is_empty() {
+            self.lanes.remove(index);
+        }
+
+        self.len -= 1;
+        task
+    }
+
+    // -----------------------------------------------------------------------
+    // remove the tasks matching 'predicate', in submission order
+
+    fn take_where(&mut self, predicate: impl Fn(&Task) -> bool) -> Vec<Task> {
+        let mut taken = Vec::new();
+
+        for (_, tasks) in self.lanes.iter_mut() {
+            let (matched, kept) = std::mem::take(tasks)
+                .into_iter()
+                .partition::<VecDeque<Task>, _>(&predicate);
+
+            taken.extend(matched);
+            *tasks = kept;
+        }
+
+        self.lanes.retain(|(_, tasks)| !tasks.is_empty());
+        self.len -= taken.len();
+        taken.sort_by_key(Task::id);
+        taken
+    }
+}
+
+// ===========================================================================
+// ** PoolShared **
+// ===========================================================================
+
+struct PoolQueue {
+    tasks: TaskQueue,
+    running: usize,
+    workers: usize,
+    shutdown: bool,
+    next_id: u64,
+    next_submitter: u64,
+    tags: HashMap<String, CancelFlag>,
+}
+
+type SortJob<T> = Box<dyn FnOnce() -> Vec<T> + Send>;
+type StartHook = Box<dyn Fn(TaskId) + Send + Sync>;
+type EndHook = Box<dyn Fn(TaskId, Duration) + Send + Sync>;
+type ContextFactory = Box<dyn Fn() -> Box<dyn Any> + Send + Sync>;
+
+thread_local! {
+    // the context created for the current worker thread by the pool's factory
+
+    static WORKER_CONTEXT: RefCell<Option<Box<dyn Any>>> = const { RefCell::new(None) };
+}
+
+// 'resumed' is open while the pool runs & closed while it's paused. it's only
+// opened or closed with the queue locked, so a worker that checks it under
+// the queue lock can't pick up a task after the pause.
+
+struct PoolShared {
+    queue: Mutex<PoolQueue>,
+    resumed: Gate,
+    task_available: Condvar,
+    space_available: Condvar,
+    idle: Condvar,
+    capacity: Option<usize>,
+    rejection_policy: RejectionPolicy,
+    fair: bool,
+    restart_count: AtomicInteger,
+    rejected_count: AtomicInteger,
+    on_task_start: Option<StartHook>,
+    on_task_end: Option<EndHook>,
+    rate_limit: Option<TokenBucket>,
+    context_factory: Option<ContextFactory>,
+    threads: Mutex<Vec<thread::JoinHandle<()>>>,
+}
+
+impl PoolShared {
+    // -----------------------------------------------------------------------
+    // queue 'task' for 'submitter', applying the rejection policy if the
+    // queue is full. tasks submitted after shutdown are dropped.
+
+    fn submit(&self, submitter: u64, mut task: Task) {
+        let mut queue = self.queue.lock().unwrap();
+        let mut dropped = Vec::new();
+
+        if queue.shutdown {
+            return;
+        }
+
+        queue.next_id += 1;
+        task.id = TaskId(queue.next_id);
+
+        if let Some(capacity) = self.capacity {
+            while queue.tasks.len() >= capacity && !queue.shutdown {
+                match &self.rejection_policy {
+                    RejectionPolicy::Block => {
+                        queue = self.space_available.wait(queue).unwrap();
+                    }
+                    RejectionPolicy::DropNewest => {
+                        drop(queue);
+                        self.rejected_count.increment();
+                        return;
+                    }
+                    RejectionPolicy::DropOldest => {
+                        dropped.extend(queue.tasks.pop_oldest());
+                        self.rejected_count.increment();
+                    }
+                    RejectionPolicy::Handler(handler) => {
+                        drop(queue);
+                        self.rejected_count.increment();
+                        handler(task);
+                        return;
+                    }
+                }
+            }
+        }
+
+        let lane = if self.fair { submitter } else { 0 };
+        queue.tasks.push(lane, task);
+        self.task_available.notify_one();
+        drop(queue);
+    }
+
+    // -----------------------------------------------------------------------
+    // block until a task is available & the pool isn't paused, or return
+    // 'None' once the pool is shutting down and the queue is empty
+
+    fn next_task(&self) -> Option<Task> {
+        let mut queue = self.queue.lock().unwrap();
+
+        loop {
+            if !self.resumed.is_open() {
+                drop(queue);
+                self.resumed.wait();
+                queue = self.queue.lock().unwrap();
+                continue;
+            }
+
+            if let Some(task) = queue.tasks.pop() {
+                queue.running += 1;
+                self.space_available.notify_one();
+                return Some(task);
+            }
+
+            if queue.shutdown {
+                return None;
+            }
+
+            queue = self.task_available.wait(queue).unwrap();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn drain(&self) -> Vec<Task> {
+        let mut queue = self.queue.lock().unwrap();
+        let tasks = queue.tasks.take_where(|_| true);
+        self.space_available.notify_all();
+
+        if queue.running == 0 {
+            self.idle.notify_all();
+        }
+
+        tasks
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn task_done(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.running -= 1;
+
+        if queue.running == 0 && queue.tasks.is_empty() {
+            self.idle.notify_all();
+        }
+    }
+}
+
+// ===========================================================================
+// ** Worker **
+// ===========================================================================
+
+struct Worker {
+    shared: Arc<PoolShared>,
+    running: bool,
+}
+
+impl Worker {
+    // -----------------------------------------------------------------------
+    // create a worker thread & save its handle in the shared thread list
+
+    fn spawn(shared: &Arc<PoolShared>) {
+        let mut worker = Worker {
+            shared: shared.clone(),
+            running: false,
+        };
+
+        shared.queue.lock().unwrap().workers += 1;
+        let handle = thread::spawn(move || worker.run());
+        shared.threads.lock().unwrap().push(handle);
+    }
+
+    // -----------------------------------------------------------------------
+    // run tasks from the queue until the pool shuts down
+
+    fn run(&mut self) {
+        if let Some(context_factory) = &self.shared.context_factory {
+            WORKER_CONTEXT.with(|context| *context.borrow_mut() = Some(context_factory()));
+        }
+
+        while let Some(task) = self.shared.next_task() {
+            let id = task.id();
+            self.running = true;
+
+            if let Some(rate_limit) = &self.shared.rate_limit {
+                rate_limit.acquire();
+            }
+
+            if let Some(on_task_start) = &self.shared.on_task_start {
+                on_task_start(id);
+            }
+
+            let start = Instant::now();
+            let complete = task.execute();
+            self.running = false;
+
+            if let Some(on_task_end) = &self.shared.on_task_end {
+                on_task_end(id, start.elapsed());
+            }
+
+            self.shared.task_done();
+            complete();
+        }
+    }
+}
+
+impl Drop for Worker {
+    // -----------------------------------------------------------------------
+    // a worker only drops while panicking if a task panicked. release the
+    // task's running slot & spawn a replacement so the pool keeps its capacity.
+
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().workers -= 1;
+
+        if !thread::panicking() {
+            return;
+        }
+
+        if self.running {
+            self.shared.task_done();
+        }
+
+        self.shared.restart_count.increment();
+        Worker::spawn(&self.shared);
+    }
+}
+
+// ===========================================================================
+// ** ThreadPoolBuilder **
+// ===========================================================================
+
+pub struct ThreadPoolBuilder {
+    thread_count: usize,
+    capacity: Option<usize>,
+    rejection_policy: RejectionPolicy,
+    fair: bool,
+    on_task_start: Option<StartHook>,
+    on_task_end: Option<EndHook>,
+    rate_limit: Option<u32>,
+    context_factory: Option<ContextFactory>,
+}
+
+impl ThreadPoolBuilder {
+    // -----------------------------------------------------------------------
+    // defaults to one thread per cpu, an unbounded queue & blocking 'put'
+
+    pub fn new() -> Self {
+        let thread_count = thread::available_parallelism().map_or(1, |n| n.get());
+
+        ThreadPoolBuilder {
+            thread_count,
+            capacity: None,
+            rejection_policy: RejectionPolicy::Block,
+            fair: false,
+            on_task_start: None,
+            on_task_end: None,
+            rate_limit: None,
+            context_factory: None,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn threads(mut self, thread_count: usize) -> Self {
+        self.thread_count = thread_count;
+        self
+    }
+
+    // -----------------------------------------------------------------------
+    // limit the number of queued (not yet running) tasks
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be at least 1");
+        self.capacity = Some(capacity);
+        self
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn rejection_policy(mut self, rejection_policy: RejectionPolicy) -> Self {
+        self.rejection_policy = rejection_policy;
+        self
+    }
+
+    // -----------------------------------------------------------------------
+    // give each 'Submitter' its own lane in the queue & start tasks from the
+    // lanes in turn, so one busy submitter can't starve the others
+
+    pub fn fair(mut self, fair: bool) -> Self {
+        self.fair = fair;
+        self
+    }
+
+    // -----------------------------------------------------------------------
+    // called on the worker thread just before each task runs
+
+    pub fn on_task_start(mut self, hook: impl Fn(TaskId) + Send + Sync + 'static) -> Self {
+        self.on_task_start = Some(Box::new(hook));
+        self
+    }
+
+    // -----------------------------------------------------------------------
+    // called on the worker thread after each task returns, with its run time
+
+    pub fn on_task_end(mut self, hook: impl Fn(TaskId, Duration) + Send + Sync + 'static) -> Self {
+        self.on_task_end = Some(Box::new(hook));
+        self
+    }
+
+    // -----------------------------------------------------------------------
+    // start at most 'per_second' tasks per second. up to 'per_second' tasks
+    // may start in a burst after the pool has been idle.
+
+    pub fn rate_limit(mut self, per_second: u32) -> Self {
+        assert!(per_second > 0, "rate limit must be at least 1");
+        self.rate_limit = Some(per_second);
+        self
+    }
+
+    // -----------------------------------------------------------------------
+    // each worker calls 'factory' once when it starts & keeps the result for
+    // the tasks queued with 'put_with_ctx'
+
+    pub fn context<C: 'static>(mut self, factory: impl Fn() -> C + Send + Sync + 'static) -> Self {
+        self.context_factory = Some(Box::new(move || Box::new(factory())));
+        self
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn build(self) -> ThreadPool {
+        let shared = Arc::new(PoolShared {
+            queue: Mutex::new(PoolQueue {
+                tasks: TaskQueue::new(),
+                running: 0,
+                workers: 0,
+                shutdown: false,
+                next_id: 0,
+                next_submitter: 1,
+                tags: HashMap::new(),
+            }),
+            resumed: Gate::new(),
+            task_available: Condvar::new(),
+            space_available: Condvar::new(),
+            idle: Condvar::new(),
+            capacity: self.capacity,
+            rejection_policy: self.rejection_policy,
+            fair: self.fair,
+            restart_count: AtomicInteger::new(0),
+            rejected_count: AtomicInteger::new(0),
+            on_task_start: self.on_task_start,
+            on_task_end: self.on_task_end,
+            rate_limit: self.rate_limit.map(TokenBucket::new),
+            context_factory: self.context_factory,
+            threads: Mutex::new(Vec::with_capacity(self.thread_count)),
+        });
+
+        shared.resumed.open();
+
+        for _ in 0..self.thread_count {
+            Worker::spawn(&shared);
+        }
+
+        ThreadPool {
+            thread_count: self.thread_count,
+            shared,
+            dedicated: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl Default for ThreadPoolBuilder {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        ThreadPoolBuilder::new()
+    }
+}
+
+// ===========================================================================
+// ** Submitter **
+// ===========================================================================
+
+#[derive(Clone)]
+pub struct Submitter {
+    shared: Arc<PoolShared>,
+    id: u64,
+}
+
+impl Submitter {
+    // -----------------------------------------------------------------------
+    // 'put' a task into the pool's queue under this submitter's lane
+
+    pub fn put<T: Send + 'static>(&self, task: impl FnOnce() -> T + Send + 'static) -> Latent<T> {
+        let (task, latent) = Task::with_latent(task);
+        self.shared.submit(self.id, task);
+        latent
+    }
+}
+
+// ===========================================================================
+// ** ThreadPool **
 // ===========================================================================
 
 pub struct ThreadPool {
     thread_count: usize,
-    threads: Vec<thread::JoinHandle<()>>,
-    task_channel: Channel<Task>,
-    running_count: Arc<AtomicInteger>,
-    empty_signal: Arc<Signal>,
+    shared: Arc<PoolShared>,
+    dedicated: Mutex<Vec<thread::JoinHandle<()>>>,
 }
 
 impl ThreadPool {
     // -----------------------------------------------------------------------
 
-    fn create_thread(&mut self) {
-        // clone struct values to be captured by the thread
+    pub fn new(thread_count: usize) -> Self {
+        ThreadPoolBuilder::new().threads(thread_count).build()
+    }
 
-        let task_channel = self.task_channel.clone();
-        let running_count = self.running_count.clone();
-        let empty_signal = self.empty_signal.clone();
-        let _id = self.threads.len();
+    // -----------------------------------------------------------------------
 
-        // create the worker thread
+    pub fn builder() -> ThreadPoolBuilder {
+        ThreadPoolBuilder::new()
+    }
 
-        let handle = thread::spawn(move || {
-            // println!("pool thread {} started", _id);
+    // -----------------------------------------------------------------------
+
+    pub fn thread_count(&self) -> usize {
+        self.thread_count
+    }
+
+    // -----------------------------------------------------------------------
+    // returns 'true' if no threads are currently running
+
+    pub fn is_empty(&self) -> bool {
+        self.shared.queue.lock().unwrap().running == 0
+    }
+
+    // -----------------------------------------------------------------------
+    // returns 'true' if all threads are currently running
+
+    pub fn is_full(&self) -> bool {
+        self.shared.queue.lock().unwrap().running == self.thread_count
+    }
+
+    // -----------------------------------------------------------------------
+    // fill the pool with the same 'task' on all threads
+
+    pub fn fill<T: Clone + Send + 'static>(
+        &self,
+        task: impl FnOnce() -> T + Send + Clone + 'static,
+    ) -> Vec<Latent<T>> {
+        let mut results = Vec::<Latent<T>>::with_capacity(self.thread_count);
+
+        for _ in 1..self.thread_count {
+            let latent = self.put(task.clone());
+            results.push(latent);
+        }
+
+        let latent = self.put(task);
+        results.push(latent);
+        results
+    }
+
+    // -----------------------------------------------------------------------
+    // 'put' a task into the pool's queue
+
+    pub fn put<T: Send + 'static>(&self, task: impl FnOnce() -> T + Send + 'static) -> Latent<T> {
+        let (task, latent) = Task::with_latent(task);
+        self.shared.submit(0, task);
+        latent
+    }
+
+    // -----------------------------------------------------------------------
+    // create a handle for a component that shares the pool. in a fair pool
+    // each submitter's tasks are started in turn with the others'.
+
+    pub fn submitter(&self) -> Submitter {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.next_submitter += 1;
+
+        Submitter {
+            shared: self.shared.clone(),
+            id: queue.next_submitter,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // a handle that puts tasks in the same lane as 'put'
+
+    pub(crate) fn default_submitter(&self) -> Submitter {
+        Submitter {
+            shared: self.shared.clone(),
+            id: 0,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // 'put' a task & return a future that resolves with its result, so async
+    // code can offload blocking work to the pool. unlike awaiting the latent
+    // from 'put', the result is moved out rather than cloned.
+
+    #[cfg(feature = "async")]
+    pub fn put_async<T: Send + 'static>(
+        &self,
+        task: impl FnOnce() -> T + Send + 'static,
+    ) -> LatentFuture<T> {
+        LatentFuture::taking(self.put(task))
+    }
+
+    // -----------------------------------------------------------------------
+    // 'put' a task that borrows the context of the worker it runs on. panics
+    // on the worker if the pool wasn't built with a 'C' context.
+
+    pub fn put_with_ctx<C: 'static, T: Send + 'static>(
+        &self,
+        task: impl FnOnce(&mut C) -> T + Send + 'static,
+    ) -> Latent<T> {
+        self.put(move || {
+            WORKER_CONTEXT.with(|context| {
+                let mut context = context.borrow_mut();
+                let ctx = context
+                    .as_mut()
+                    .and_then(|context| context.downcast_mut::<C>())
+                    .expect("no worker context of the requested type");
+
+                task(ctx)
+            })
+        })
+    }
+
+    // -----------------------------------------------------------------------
+    // wait for every latent in a batch of fallible tasks & return the errors
+    // in the order the latents were given. a task that returns a 'Result' is
+    // just 'put', so its latent already tells a failure from a success.
+
+    pub fn collect_errors<T, E: Clone>(&self, latents: &[Latent<Result<T, E>>]) -> Vec<E> {
+        latents.iter().filter_map(Latent::wait_err).collect()
+    }
+
+    // -----------------------------------------------------------------------
+    // 'put' a task that can be cancelled along with every other task sharing
+    // its 'tag'. the task is passed a flag it should check periodically.
+
+    pub fn put_tagged<T: Send + 'static>(
+        &self,
+        tag: &str,
+        task: impl FnOnce(&CancelFlag) -> T + Send + 'static,
+    ) -> Latent<T> {
+        let cancel_flag = {
+            let mut queue = self.shared.queue.lock().unwrap();
+
+            // forget the tags whose tasks have all finished or been dropped
+
+            queue.tags.retain(|_, cancel_flag| cancel_flag.is_shared());
+            queue
+                .tags
+                .entry(tag.to_string())
+                .or_insert_with(CancelFlag::new)
+                .clone()
+        };
+
+        let (setter, latent) = Latent::<T>::pair();
+        let t = move || -> Completion {
+            // the tag may have been cancelled after the task was queued
+
+            if cancel_flag.is_cancelled() {
+                return Box::new(move || drop(setter));
+            }
+
+            let r = task(&cancel_flag);
+            Box::new(move || setter.set(r))
+        };
+
+        let mut task_info = Task::new(t);
+        task_info.tag = Some(tag.to_string());
+        self.shared.submit(0, task_info);
+        latent
+    }
+
+    // -----------------------------------------------------------------------
+    // drop the queued tasks with 'tag' & raise the cancel flag of the running
+    // ones. returns the number of queued tasks that were dropped. tasks put
+    // with 'tag' afterwards start with a fresh flag.
+
+    pub fn cancel_tag(&self, tag: &str) -> usize {
+        let mut queue = self.shared.queue.lock().unwrap();
+
+        if let Some(cancel_flag) = queue.tags.remove(tag) {
+            cancel_flag.cancel();
+        }
+
+        let dropped = queue.tasks.take_where(|task| task.tag() == Some(tag));
+        self.shared.space_available.notify_all();
+
+        if queue.running == 0 && queue.tasks.is_empty() {
+            self.shared.idle.notify_all();
+        }
+
+        drop(queue);
+        dropped.len()
+    }
+
+    // -----------------------------------------------------------------------
+    // remove & return every queued task that hasn't started yet. the caller
+    // can run, persist or log them; dropping them leaves their latents unset.
+
+    pub fn drain(&self) -> Vec<Task> {
+        self.shared.drain()
+    }
+
+    // -----------------------------------------------------------------------
+    // drop the queued tasks when 'token' is stopped, abandoning their
+    // latents. running tasks finish unless they check the token themselves.
+
+    pub fn stop_on(&self, token: &StopToken) {
+        token.on_stop_weak(&self.shared, |shared| drop(shared.drain()));
+    }
+
+    // -----------------------------------------------------------------------
+    // reduce 'items' to a single value across the workers. each worker folds
+    // a chunk of the items, then the partial results are combined pairwise
+    // until one remains. 'op' must be associative and 'identity' must leave
+    // a value unchanged when combined with it.
+
+    pub fn reduce<T: Clone + Send + 'static>(
+        &self,
+        items: Vec<T>,
+        identity: T,
+        op: impl Fn(T, T) -> T + Send + Sync + 'static,
+    ) -> T {
+        let op = Arc::new(op);
+        let chunk_size = items.len().div_ceil(self.thread_count.max(1)).max(1);
+        let mut items = items.into_iter();
+        let mut partials = Vec::<Latent<T>>::new();
+
+        // fold each chunk on a worker
+
+        loop {
+            let chunk = items.by_ref().take(chunk_size).collect::<Vec<T>>();
+
+            if chunk.is_empty() {
+                break;
+            }
+
+            let op = op.clone();
+            let identity = identity.clone();
+            partials.push(self.put(move || chunk.into_iter().fold(identity, |a, b| op(a, b))));
+        }
+
+        // combine the partial results pairwise, one level of the tree at a time
+
+        while partials.len() > 1 {
+            let mut values = partials.into_iter().map(|latent| latent.wait());
+            partials = Vec::new();
+
+            while let Some(a) = values.next() {
+                let op = op.clone();
+
+                match values.next() {
+                    Some(b) => partials.push(self.put(move || op(a, b))),
+                    None => partials.push(Latent::ready(a)),
+                }
+            }
+        }
+
+        match partials.pop() {
+            Some(latent) => latent.wait(),
+            None => identity,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // stable parallel merge sort. 'items' is split into one run per worker,
+    // each run is sorted on a worker, then the runs are merged pairwise on
+    // the pool until one remains.
+
+    pub fn sort<T: Send + 'static>(
+        &self,
+        items: &mut Vec<T>,
+        cmp: impl Fn(&T, &T) -> Ordering + Send + Sync + 'static,
+    ) {
+        if self.thread_count < 2 || items.len() < 2 {
+            items.sort_by(cmp);
+            return;
+        }
+
+        let cmp = Arc::new(cmp);
+        let run_size = items.len().div_ceil(self.thread_count);
+        let mut rest = std::mem::take(items);
+        let mut jobs = Vec::<SortJob<T>>::new();
+
+        // sort each run on a worker
+
+        while !rest.is_empty() {
+            let tail = rest.split_off(run_size.min(rest.len()));
+            let mut run = std::mem::replace(&mut rest, tail);
+            let cmp = cmp.clone();
+
+            jobs.push(Box::new(move || {
+                run.sort_by(|a, b| cmp(a, b));
+                run
+            }));
+        }
+
+        let mut runs = self.run_sort_jobs(jobs);
+
+        // merge the runs pairwise until only one is left
+
+        while runs.len() > 1 {
+            let mut runs_iter = runs.into_iter();
+            let mut jobs = Vec::<SortJob<T>>::new();
+
+            while let Some(a) = runs_iter.next() {
+                match runs_iter.next() {
+                    Some(b) => {
+                        let cmp = cmp.clone();
+                        jobs.push(Box::new(move || merge_runs(a, b, &*cmp)));
+                    }
+                    None => jobs.push(Box::new(move || a)),
+                }
+            }
+
+            runs = self.run_sort_jobs(jobs);
+        }
+
+        *items = runs.pop().unwrap_or_default();
+    }
+
+    // -----------------------------------------------------------------------
+    // run the 'jobs' on the workers & return their results in the same order
+
+    fn run_sort_jobs<T: Send + 'static>(&self, jobs: Vec<SortJob<T>>) -> Vec<Vec<T>> {
+        let results = Channel::<(usize, Vec<T>)>::new();
+        let mut runs = Vec::with_capacity(jobs.len());
+
+        for (index, job) in jobs.into_iter().enumerate() {
+            let results = results.clone();
+            self.put(move || results.put((index, job())).is_ok());
+            runs.push(Vec::new());
+        }
 
-            // wait for a task from the channel
+        for _ in 0..runs.len() {
+            let (index, run) = results.get().expect("sort task was dropped by the pool");
+            runs[index] = run;
+        }
 
-            while let Some(task) = task_channel.get() {
-                running_count.increment();
-                // let _result = task();
-                let _result = (task.func)();
-                running_count.decrement();
+        runs
+    }
 
-                if running_count.get() == 0 {
-                    empty_signal.signal_all();
-                }
-            }
+    // -----------------------------------------------------------------------
+    // run 'task' on a dedicated thread outside of the worker threads. use this
+    // for long-lived loops so they don't starve the tasks queued with 'put'.
+    // the thread is tracked by the pool and joined when the pool is dropped.
 
-            // println!("pool thread {} exited", _id);
+    pub fn spawn_dedicated<T: Send + 'static>(
+        &self,
+        task: impl FnOnce() -> T + Send + 'static,
+    ) -> Latent<T> {
+        let (setter, latent) = Latent::<T>::pair();
+        let handle = thread::spawn(move || {
+            let r = task();
+            setter.set(r);
         });
 
-        // save the thread handle
+        self.dedicated.lock().unwrap().push(handle);
+        latent
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the number of dedicated threads that are still running
 
-        self.threads.push(handle);
+    pub fn dedicated_count(&self) -> usize {
+        let dedicated = self.dedicated.lock().unwrap();
+        dedicated
+            .iter()
+            .filter(|handle| !handle.is_finished())
+            .count()
     }
 
     // -----------------------------------------------------------------------
+    // stop the workers from starting queued tasks by closing the 'resumed'
+    // gate. running tasks finish and 'put' keeps queueing.
 
-    pub fn new(thread_count: usize) -> Self {
-        let mut pool = ThreadPool {
-            thread_count,
-            threads: Vec::with_capacity(thread_count),
-            task_channel: Channel::named("ThreadPool"),
-            running_count: Arc::new(AtomicInteger::new(0)),
-            empty_signal: Arc::new(Signal::new()),
-        };
+    pub fn pause(&self) {
+        let _queue = self.shared.queue.lock().unwrap();
+        self.shared.resumed.close();
+    }
 
-        for _ in 0..thread_count {
-            pool.create_thread();
-        }
+    // -----------------------------------------------------------------------
+    // let the workers start queued tasks again
 
-        pool
+    pub fn resume(&self) {
+        let _queue = self.shared.queue.lock().unwrap();
+        self.shared.resumed.open();
     }
 
     // -----------------------------------------------------------------------
 
-    pub fn thread_count(&self) -> usize {
-        self.thread_count
+    pub fn is_paused(&self) -> bool {
+        !self.shared.resumed.is_open()
     }
 
     // -----------------------------------------------------------------------
-    // returns 'true' if no threads are currently running
+    // returns the number of queued tasks, running tasks & idle workers
 
-    pub fn is_empty(&self) -> bool {
-        self.running_count.get() == 0
+    pub fn state(&self) -> PoolState {
+        let queue = self.shared.queue.lock().unwrap();
+
+        PoolState {
+            queued: queue.tasks.len(),
+            running: queue.running,
+            idle: queue.workers.saturating_sub(queue.running),
+        }
     }
 
     // -----------------------------------------------------------------------
-    // returns 'true' if all threads are currently running
+    // returns the pool's counters
 
-    pub fn is_full(&self) -> bool {
-        self.running_count.get() == self.thread_count as i32
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            restarts: self.shared.restart_count.get() as usize,
+            rejected: self.shared.rejected_count.get() as usize,
+        }
     }
 
     // -----------------------------------------------------------------------
-    // fill the pool with the same 'task' on all threads
+    // wait for all queued & running tasks to complete. blocks until 'resume'
+    // if the pool is paused with tasks still queued.
 
-    pub fn fill<T: Clone + Send + 'static>(
-        &self,
-        task: impl FnOnce() -> T + Send + Clone + 'static,
-    ) -> Vec<Latent<T>> {
-        let mut results = Vec::<Latent<T>>::with_capacity(self.thread_count);
+    pub fn wait(&self) {
+        let mut queue = self.shared.queue.lock().unwrap();
 
-        for _ in 1..self.thread_count {
-            let latent = self.put(task.clone());
-            results.push(latent);
+        while queue.running > 0 || !queue.tasks.is_empty() {
+            queue = self.shared.idle.wait(queue).unwrap();
         }
-
-        let latent = self.put(task);
-        results.push(latent);
-        results
     }
+}
 
+impl Drop for ThreadPool {
     // -----------------------------------------------------------------------
-    // 'put' a task into the pool's queue
+    // shut down the queue so the workers exit once it is drained, then join
+    // the workers & any dedicated threads. a paused pool drops its queued
+    // tasks instead of starting them, & opens the gate to let the workers out.
 
-    pub fn put<T: Clone + Send + 'static>(
-        &self,
-        task: impl FnOnce() -> T + Send + 'static,
-    ) -> Latent<T> {
-        let latent = Latent::<T>::new();
-        let l = latent.clone();
-        let t = move || {
-            let r = task();
-            l.set(r);
+    fn drop(&mut self) {
+        let dropped = {
+            let mut queue = self.shared.queue.lock().unwrap();
+            queue.shutdown = true;
+
+            let dropped = match self.shared.resumed.is_open() {
+                true => Vec::new(),
+                false => queue.tasks.take_where(|_| true),
+            };
+
+            self.shared.resumed.open();
+            self.shared.task_available.notify_all();
+            self.shared.space_available.notify_all();
+            dropped
         };
 
-        let task_info = Task::new(t);
-        self.task_channel.put(task_info);
-        latent
-    }
+        drop(dropped);
 
-    // -----------------------------------------------------------------------
-    // wait for all tasks to complete
+        // a panicking worker may add its replacement while we're joining
 
-    pub fn wait(&self) {
-        if self.running_count.get() > 0 {
-            self.empty_signal.wait();
+        loop {
+            let threads = std::mem::take(&mut *self.shared.threads.lock().unwrap());
+
+            if threads.is_empty() {
+                break;
+            }
+
+            for handle in threads {
+                let _ = handle.join();
+            }
         }
+
+        let dedicated = std::mem::take(&mut *self.dedicated.lock().unwrap());
+
+        for handle in dedicated {
+            let _ = handle.join();
+        }
+    }
+}
+
+// ===========================================================================
+// ** merge_runs **
+// ===========================================================================
+
+// merge two sorted runs, taking from 'a' on ties to keep the sort stable
+
+fn merge_runs<T, F: Fn(&T, &T) -> Ordering>(a: Vec<T>, b: Vec<T>, cmp: &F) -> Vec<T> {
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let mut a = a.into_iter().peekable();
+    let mut b = b.into_iter().peekable();
+
+    loop {
+        let take_b = match (a.peek(), b.peek()) {
+            (Some(x), Some(y)) => cmp(y, x) == Ordering::Less,
+            (Some(_), None) => false,
+            (None, Some(_)) => true,
+            (None, None) => break,
+        };
+
+        merged.extend(if take_b { b.next() } else { a.next() });
     }
+
+    merged
 }
 
 // ===========================================================================
@@ -157,7 +1228,24 @@ impl ThreadPool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::Duration;
+    use crate::thread::LatentError;
+
+    // -----------------------------------------------------------------------
+    // occupy one of the pool's workers until the returned gate opens
+
+    fn block_worker(pool: &ThreadPool) -> Arc<Gate> {
+        let gate = Gate::arc();
+        let g = gate.clone();
+        let started = Latent::<()>::new();
+        let s = started.clone();
+        pool.put(move || {
+            s.set(());
+            g.wait();
+        });
+
+        started.wait();
+        gate
+    }
 
     // -----------------------------------------------------------------------
 
@@ -171,10 +1259,10 @@ mod tests {
         let outgoing = Channel::<i32>::new();
         let incoming = outgoing.clone();
         let worker = move || {
-            while let Some(item) = incoming.get() {
+            while let Ok(item) = incoming.get() {
                 thread::sleep(Duration::from_millis(1000));
                 let new_item = item + 1;
-                results_tx.put(new_item);
+                results_tx.put(new_item).unwrap();
                 // println!("tid: {:?}, item: {}", thread::current().id(), item);
             }
         };
@@ -182,13 +1270,13 @@ mod tests {
         pool.fill(worker);
 
         for _ in 0..threads * 2 {
-            outgoing.put(0);
+            outgoing.put(0).unwrap();
         }
 
         drop(outgoing);
         let mut sum = 0;
 
-        while let Some(item) = results_rx.get() {
+        while let Ok(item) = results_rx.get() {
             println!("*** result: {}", item);
             sum += item;
         }
@@ -239,12 +1327,12 @@ mod tests {
         };
 
         let latent_39 = pool.put(task_39);
-        assert!(latent_39.is_ready() == false);
+        assert!(!latent_39.is_ready());
 
         let value_42 = pool.put(task_42).wait();
         assert_eq!(value_42, 42);
 
-        assert!(latent_39.is_ready() == true);
+        assert!(latent_39.is_ready());
         let value_39 = latent_39.wait();
         assert_eq!(value_39, 39);
 
@@ -253,13 +1341,429 @@ mod tests {
         };
 
         let none = pool.put(task_none);
-        assert!(none.is_ready() == false);
+        assert!(!none.is_ready());
 
-        let none_value = none.wait();
-        assert!(none_value == ());
+        none.wait();
 
         pool.put(task_42);
         pool.wait();
         assert!(pool.is_empty());
     }
+
+    // -----------------------------------------------------------------------
+    // test that a dedicated loop doesn't starve 'put' & is joined on drop
+
+    #[test]
+    fn validate_threadpool_dedicated() {
+        let pool = ThreadPool::new(1);
+        let outgoing = Channel::<i32>::new();
+        let incoming = outgoing.clone();
+        let total = Arc::new(AtomicInteger::new(0));
+        let t = total.clone();
+
+        let looper = pool.spawn_dedicated(move || {
+            while let Ok(item) = incoming.get() {
+                t.add(item);
+            }
+        });
+
+        assert_eq!(pool.put(|| 42).wait(), 42);
+
+        outgoing.put(1).unwrap();
+        outgoing.put(2).unwrap();
+        outgoing.end();
+        looper.wait();
+
+        drop(pool);
+        assert_eq!(total.get(), 3);
+    }
+
+    // -----------------------------------------------------------------------
+    // test that a worker killed by a panicking task is replaced
+
+    #[test]
+    fn validate_threadpool_restart() {
+        let pool = ThreadPool::new(1);
+        pool.put(|| panic!("task failure"));
+
+        assert_eq!(pool.put(|| 42).wait(), 42);
+        assert_eq!(pool.stats().restarts, 1);
+        assert!(pool.is_empty());
+    }
+
+    // -----------------------------------------------------------------------
+    // test that a full pool hands new tasks to the rejection handler
+
+    #[test]
+    fn validate_threadpool_rejection() {
+        let rejected = Arc::new(Mutex::new(Vec::<Task>::new()));
+        let r = rejected.clone();
+        let pool = ThreadPool::builder()
+            .threads(1)
+            .capacity(1)
+            .rejection_policy(RejectionPolicy::Handler(Arc::new(move |task| {
+                r.lock().unwrap().push(task);
+            })))
+            .build();
+
+        // occupy the only worker until the gate opens
+
+        let gate = block_worker(&pool);
+
+        let queued = pool.put(|| 1);
+        let rejected_latent = pool.put(|| 2);
+        assert_eq!(pool.stats().rejected, 1);
+
+        // run the rejected task on this thread
+
+        let task = rejected.lock().unwrap().pop().unwrap();
+        task.run();
+        assert_eq!(rejected_latent.wait(), 2);
+
+        gate.open();
+        assert_eq!(queued.wait(), 1);
+    }
+
+    // -----------------------------------------------------------------------
+    // test that the start & end hooks see every task
+
+    #[test]
+    fn validate_threadpool_hooks() {
+        let started = Arc::new(Mutex::new(Vec::<TaskId>::new()));
+        let ended = Arc::new(Mutex::new(Vec::<(TaskId, Duration)>::new()));
+        let s = started.clone();
+        let e = ended.clone();
+        let pool = ThreadPool::builder()
+            .threads(2)
+            .on_task_start(move |id| s.lock().unwrap().push(id))
+            .on_task_end(move |id, elapsed| e.lock().unwrap().push((id, elapsed)))
+            .build();
+
+        pool.put(|| thread::sleep(Duration::from_millis(20)));
+        pool.put(|| ());
+        pool.wait();
+
+        let mut started = started.lock().unwrap().clone();
+        started.sort();
+        assert_eq!(started, vec![TaskId(1), TaskId(2)]);
+
+        let ended = ended.lock().unwrap();
+        assert_eq!(ended.len(), 2);
+
+        let slow = ended.iter().find(|(id, _)| *id == TaskId(1)).unwrap();
+        assert!(slow.1 >= Duration::from_millis(20));
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_threadpool_reduce() {
+        let pool = ThreadPool::new(4);
+        let items = (1..=1000).collect::<Vec<u64>>();
+        assert_eq!(pool.reduce(items, 0, |a, b| a + b), 500500);
+
+        let words = vec!["a", "b", "c", "d", "e"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        assert_eq!(pool.reduce(words, String::new(), |a, b| a + &b), "abcde");
+        assert_eq!(pool.reduce(Vec::<u64>::new(), 7, |a, b| a + b), 7);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_threadpool_sort() {
+        let pool = ThreadPool::new(3);
+        let mut seed = 12345u64;
+        let mut items = Vec::<(u64, usize)>::new();
+
+        for i in 0..10_000 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            items.push(((seed >> 33) % 100, i));
+        }
+
+        // sorting on the key only must keep equal keys in their original order
+
+        let mut expected = items.clone();
+        expected.sort_by_key(|item| item.0);
+
+        pool.sort(&mut items, |a, b| a.0.cmp(&b.0));
+        assert_eq!(items, expected);
+
+        let mut small = vec![3, 1, 2];
+        pool.sort(&mut small, |a, b| b.cmp(a));
+        assert_eq!(small, vec![3, 2, 1]);
+    }
+
+    // -----------------------------------------------------------------------
+    // test that task starts are capped once the initial burst is used up
+
+    #[test]
+    fn validate_threadpool_rate_limit() {
+        let pool = ThreadPool::builder().threads(4).rate_limit(20).build();
+        let start = Instant::now();
+
+        for _ in 0..30 {
+            pool.put(|| ());
+        }
+
+        pool.wait();
+
+        // 20 tokens for the burst, then 10 more at 20 per second
+
+        assert!(start.elapsed() >= Duration::from_millis(400));
+    }
+
+    // -----------------------------------------------------------------------
+    // test that cancelling a tag drops its queued tasks & flags running ones
+
+    #[test]
+    fn validate_threadpool_cancel_tag() {
+        let pool = ThreadPool::new(1);
+        let started = Latent::<()>::new();
+        let s = started.clone();
+
+        let running = pool.put_tagged("download", move |cancel_flag| {
+            s.set(());
+
+            while !cancel_flag.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            7
+        });
+
+        started.wait();
+
+        let queued = (0..3)
+            .map(|i| pool.put_tagged("download", move |_| i))
+            .collect::<Vec<Latent<i32>>>();
+
+        let other = pool.put(|| 42);
+
+        assert_eq!(pool.cancel_tag("download"), 3);
+        assert_eq!(running.wait(), 7);
+        assert_eq!(other.wait(), 42);
+
+        pool.wait();
+        assert!(queued.iter().all(|latent| latent.is_abandoned()));
+        assert_eq!(pool.put_tagged("download", |_| 1).wait(), 1);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_threadpool_drain() {
+        let pool = ThreadPool::new(1);
+        let gate = block_worker(&pool);
+
+        let first = pool.put(|| 1);
+        let second = pool.put(|| 2);
+        let mut tasks = pool.drain();
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks[0].id() < tasks[1].id());
+
+        tasks.remove(1).run();
+        assert_eq!(second.wait(), 2);
+
+        gate.open();
+        pool.wait();
+        assert!(!first.is_ready());
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn validate_threadpool_put_async() {
+        use crate::thread::block_on;
+
+        let pool = ThreadPool::new(2);
+        let future = pool.put_async(|| {
+            thread::sleep(Duration::from_millis(50));
+            42
+        });
+
+        assert_eq!(block_on(future), 42);
+        assert_eq!(block_on(pool.put_async(|| "done")), "done");
+
+        // the result needn't be 'Clone'
+        let mutex = block_on(pool.put_async(|| Mutex::new(7)));
+        assert_eq!(*mutex.lock().unwrap(), 7);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_threadpool_collect_errors() {
+        let pool = ThreadPool::new(2);
+        let latents = (0..5)
+            .map(|i| {
+                pool.put(move || match i % 2 {
+                    0 => Ok(i),
+                    _ => Err(format!("task {} failed", i)),
+                })
+            })
+            .collect::<Vec<Latent<Result<i32, String>>>>();
+
+        let errors = pool.collect_errors(&latents);
+        assert_eq!(errors, vec!["task 1 failed", "task 3 failed"]);
+        assert_eq!(latents[4].clone().wait(), Ok(4));
+    }
+
+    // -----------------------------------------------------------------------
+    // test that each worker creates its context once & reuses it
+
+    #[test]
+    fn validate_threadpool_context() {
+        let created = Arc::new(AtomicInteger::new(0));
+        let c = created.clone();
+        let pool = ThreadPool::builder()
+            .threads(2)
+            .context(move || {
+                c.increment();
+                Vec::<u8>::with_capacity(1024)
+            })
+            .build();
+
+        let latents = (0..10)
+            .map(|i| {
+                pool.put_with_ctx(move |buffer: &mut Vec<u8>| {
+                    buffer.push(i);
+                    buffer.capacity()
+                })
+            })
+            .collect::<Vec<Latent<usize>>>();
+
+        for latent in latents {
+            assert!(latent.wait() >= 1024);
+        }
+
+        // a worker that hasn't started yet may not have created its context
+
+        assert!(created.get() >= 1 && created.get() <= 2);
+    }
+
+    // -----------------------------------------------------------------------
+    // test that a paused pool finishes running tasks but doesn't start new ones
+
+    #[test]
+    fn validate_threadpool_pause() {
+        let pool = ThreadPool::new(2);
+        let started = Latent::<()>::new();
+        let s = started.clone();
+        let running = pool.put(move || {
+            s.set(());
+            thread::sleep(Duration::from_millis(50));
+            1
+        });
+
+        started.wait();
+        pool.pause();
+        assert!(pool.is_paused());
+
+        let queued = pool.put(|| 2);
+        assert_eq!(running.wait(), 1);
+
+        thread::sleep(Duration::from_millis(50));
+        assert!(!queued.is_ready());
+
+        pool.resume();
+        assert_eq!(queued.wait(), 2);
+
+        // dropping a paused pool drops its queued tasks without starting them
+        pool.pause();
+        let dropped = pool.put(|| 3);
+        drop(pool);
+        assert_eq!(dropped.try_wait(), Err(LatentError::Abandoned));
+    }
+
+    // -----------------------------------------------------------------------
+    // test that a fair pool alternates between its submitters
+
+    #[test]
+    fn validate_threadpool_fair() {
+        let pool = ThreadPool::builder().threads(1).fair(true).build();
+        let gate = block_worker(&pool);
+
+        let order = Arc::new(Mutex::new(Vec::<String>::new()));
+        let chatty = pool.submitter();
+        let quiet = pool.submitter();
+
+        for i in 1..=4 {
+            let order = order.clone();
+            chatty.put(move || order.lock().unwrap().push(format!("a{}", i)));
+        }
+
+        for i in 1..=2 {
+            let order = order.clone();
+            quiet.put(move || order.lock().unwrap().push(format!("b{}", i)));
+        }
+
+        gate.open();
+        pool.wait();
+
+        let order = order.lock().unwrap();
+        assert_eq!(*order, vec!["a1", "b1", "a2", "b2", "a3", "a4"]);
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_threadpool_state() {
+        let pool = ThreadPool::new(2);
+        let gate = block_worker(&pool);
+        pool.pause();
+
+        for _ in 0..3 {
+            pool.put(|| ());
+        }
+
+        let expected = PoolState {
+            queued: 3,
+            running: 1,
+            idle: 1,
+        };
+
+        assert_eq!(pool.state(), expected);
+
+        gate.open();
+        pool.resume();
+        pool.wait();
+
+        let expected = PoolState {
+            queued: 0,
+            running: 0,
+            idle: 2,
+        };
+
+        assert_eq!(pool.state(), expected);
+    }
+
+    // -----------------------------------------------------------------------
+    // test that the flags of finished tags don't pile up
+
+    #[test]
+    fn validate_threadpool_tag_prune() {
+        let pool = ThreadPool::new(2);
+
+        for i in 0..10 {
+            pool.put_tagged(&format!("job{}", i), |_| ()).wait();
+        }
+
+        assert_eq!(pool.shared.queue.lock().unwrap().tags.len(), 1);
+
+        let gate = Gate::arc();
+        let g = gate.clone();
+        let running = pool.put_tagged("slow", move |_| g.wait());
+        pool.put_tagged("fast", |_| ()).wait();
+
+        let tags = pool.shared.queue.lock().unwrap().tags.len();
+        assert_eq!(tags, 2);
+
+        gate.open();
+        running.wait();
+    }
 }
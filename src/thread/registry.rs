@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, Thread};
+
+// ===========================================================================
+// ** ThreadRegistry **
+// ===========================================================================
+
+// long-lived threads register under a name so other threads can unpark them
+// by that name, or list which of them are still alive. a thread stays
+// registered until its 'Registration' is dropped, which happens on exit or
+// panic when the guard is held by the thread itself.
+
+pub struct ThreadRegistry {
+    threads: Arc<Mutex<HashMap<String, Thread>>>,
+}
+
+impl ThreadRegistry {
+    // -----------------------------------------------------------------------
+
+    pub fn new() -> Self {
+        ThreadRegistry {
+            threads: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // register the current thread as 'name'. returns 'None' if another live
+    // thread already holds the name.
+
+    pub fn register(&self, name: &str) -> Option<Registration> {
+        let mut threads = self.threads.lock().unwrap();
+
+        if threads.contains_key(name) {
+            return None;
+        }
+
+        threads.insert(name.to_string(), thread::current());
+
+        Some(Registration {
+            name: name.to_string(),
+            threads: self.threads.clone(),
+        })
+    }
+
+    // -----------------------------------------------------------------------
+    // unpark the thread registered as 'name'. returns 'false' if there is
+    // no such thread.
+
+    pub fn unpark(&self, name: &str) -> bool {
+        match self.threads.lock().unwrap().get(name) {
+            Some(thread) => {
+                thread.unpark();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the sorted names of the registered threads
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.threads.lock().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.threads.lock().unwrap().contains_key(name)
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn len(&self) -> usize {
+        self.threads.lock().unwrap().len()
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Clone for ThreadRegistry {
+    // -----------------------------------------------------------------------
+
+    fn clone(&self) -> Self {
+        ThreadRegistry {
+            threads: self.threads.clone(),
+        }
+    }
+}
+
+impl Default for ThreadRegistry {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        ThreadRegistry::new()
+    }
+}
+
+// ===========================================================================
+// ** Registration **
+// ===========================================================================
+
+pub struct Registration {
+    name: String,
+    threads: Arc<Mutex<HashMap<String, Thread>>>,
+}
+
+impl Registration {
+    // -----------------------------------------------------------------------
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Drop for Registration {
+    // -----------------------------------------------------------------------
+
+    fn drop(&mut self) {
+        self.threads.lock().unwrap().remove(&self.name);
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::{AtomicInteger, Latent};
+    use std::time::Duration;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_thread_registry() {
+        let registry = ThreadRegistry::new();
+        let wakeups = Arc::new(AtomicInteger::new(0));
+        let (setter, registered) = Latent::<()>::pair();
+
+        let handle = {
+            let registry = registry.clone();
+            let wakeups = wakeups.clone();
+
+            thread::spawn(move || {
+                let _registration = registry.register("indexer").unwrap();
+                setter.set(());
+
+                while wakeups.get() == 0 {
+                    thread::park();
+                }
+            })
+        };
+
+        registered.wait();
+        assert!(registry.register("indexer").is_none());
+        assert_eq!(registry.names(), vec!["indexer".to_string()]);
+
+        wakeups.increment();
+        assert!(registry.unpark("indexer"));
+        handle.join().unwrap();
+
+        // the registration is dropped when the thread exits
+
+        assert!(!registry.contains("indexer"));
+        assert!(!registry.unpark("indexer"));
+        assert!(registry.is_empty());
+
+        let registration = registry.register("main").unwrap();
+        assert_eq!(registration.name(), "main");
+        assert!(registry.unpark("main"));
+        thread::park_timeout(Duration::from_millis(20));
+    }
+}
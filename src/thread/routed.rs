@@ -0,0 +1,132 @@
+use crate::thread::Channel;
+use crate::thread::SendError;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+// ===========================================================================
+// ** RoutedChannel **
+// ===========================================================================
+
+// spreads items over several consumer channels by key. items with the same
+// key always go to the same channel, so they stay in order while different
+// keys are handled by different workers.
+
+pub struct RoutedChannel<K, T> {
+    routes: Vec<Channel<T>>,
+    _key: PhantomData<fn(&K)>,
+}
+
+impl<K: Hash, T> RoutedChannel<K, T> {
+    // -----------------------------------------------------------------------
+
+    pub fn new(route_count: usize) -> Self {
+        assert!(route_count > 0, "route count must be at least 1");
+
+        RoutedChannel {
+            routes: (0..route_count).map(|_| Channel::new()).collect(),
+            _key: PhantomData,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn route_count(&self) -> usize {
+        self.routes.len()
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the index of the channel that items with 'key' are put into
+
+    pub fn route_of(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish() as usize % self.routes.len()
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a consumer handle for the channel at 'index'
+
+    pub fn receiver(&self, index: usize) -> Channel<T> {
+        self.routes[index].clone()
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a consumer handle for each channel, in route order
+
+    pub fn receivers(&self) -> Vec<Channel<T>> {
+        self.routes.clone()
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn put(&self, key: &K, item: T) -> Result<(), SendError<T>> {
+        self.routes[self.route_of(key)].put(item)
+    }
+
+    // -----------------------------------------------------------------------
+    // close every channel, so each consumer gets 'RecvError::Closed' once
+    // its items are taken
+
+    pub fn close(&self) {
+        for route in self.routes.iter() {
+            route.close();
+        }
+    }
+}
+
+impl<K, T> Clone for RoutedChannel<K, T> {
+    // -----------------------------------------------------------------------
+
+    fn clone(&self) -> Self {
+        RoutedChannel {
+            routes: self.routes.clone(),
+            _key: PhantomData,
+        }
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_routed_channel() {
+        let routed = RoutedChannel::<String, (String, i32)>::new(3);
+        let mut handles = Vec::new();
+
+        for receiver in routed.receivers() {
+            handles.push(thread::spawn(move || {
+                std::iter::from_fn(|| receiver.get().ok()).collect::<Vec<_>>()
+            }));
+        }
+
+        for i in 0..30 {
+            let key = format!("key{}", i % 5);
+            routed.put(&key, (key.clone(), i)).unwrap();
+        }
+
+        routed.close();
+
+        for (index, handle) in handles.into_iter().enumerate() {
+            let items = handle.join().unwrap();
+
+            for (key, _) in items.iter() {
+                assert_eq!(routed.route_of(key), index);
+                let order: Vec<i32> = items
+                    .iter()
+                    .filter(|(k, _)| k == key)
+                    .map(|(_, i)| *i)
+                    .collect();
+                assert!(order.is_sorted());
+            }
+        }
+    }
+}
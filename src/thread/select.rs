@@ -0,0 +1,359 @@
+use crate::thread::Channel;
+use crate::thread::Event;
+use crate::thread::EventListener;
+use crate::thread::Gate;
+use crate::thread::Latent;
+use crate::thread::LatentWaiter;
+use crate::thread::channel::ChannelWait;
+
+// ===========================================================================
+// ** Select **
+// ===========================================================================
+
+// waits on several channels, latents, gates & event listeners, of any item
+// type, until one of them is ready. a ready channel has items queued, has
+// ended, or has no other handle left that could put an item, so 'get' won't
+// block unless another consumer takes the item first. a latent is ready once
+// it is resolved, a gate once it is open & a listener once 'wait_one' won't
+// block.
+
+pub struct Select<'a> {
+    channels: Vec<&'a dyn ChannelWait>,
+}
+
+impl<'a> Select<'a> {
+    // -----------------------------------------------------------------------
+
+    pub fn new() -> Self {
+        Select {
+            channels: Vec::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // add 'channel' & return the index 'wait' reports when it is ready
+
+    pub fn add<T>(&mut self, channel: &'a Channel<T>) -> usize {
+        self.channels.push(channel);
+        self.channels.len() - 1
+    }
+
+    // -----------------------------------------------------------------------
+    // add 'latent' & return the index 'wait' reports when it is resolved
+
+    pub fn add_latent<T>(&mut self, latent: &'a Latent<T>) -> usize {
+        self.channels.push(latent);
+        self.channels.len() - 1
+    }
+
+    // -----------------------------------------------------------------------
+    // add 'gate' & return the index 'wait' reports when it is open
+
+    pub fn add_gate(&mut self, gate: &'a Gate) -> usize {
+        self.channels.push(gate);
+        self.channels.len() - 1
+    }
+
+    // -----------------------------------------------------------------------
+    // add 'listener' & return the index 'wait' reports when an event fires
+
+    pub fn add_listener<T>(&mut self, listener: &'a EventListener<T>) -> usize {
+        self.channels.push(listener);
+        self.channels.len() - 1
+    }
+
+    // -----------------------------------------------------------------------
+    // block until one of the channels is ready & return its index. returns
+    // 'None' if no channels were added.
+
+    pub fn wait(&self) -> Option<usize> {
+        if let Some(index) = self.channels.iter().position(|channel| channel.is_ready()) {
+            return Some(index);
+        }
+
+        let listener_id = LatentWaiter::token();
+        let mut listener = EventListener::<usize>::new();
+
+        for (i, channel) in self.channels.iter().enumerate() {
+            channel.add_event(listener.create_event(i), listener_id);
+        }
+
+        let index = listener.wait_one();
+
+        for channel in self.channels.iter() {
+            channel.remove_event(listener_id);
+        }
+
+        index
+    }
+}
+
+impl Default for Select<'_> {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        Select::new()
+    }
+}
+
+// ===========================================================================
+// ** SelectArms **
+// ===========================================================================
+
+// a select that runs an arm for whichever source is ready first. each arm is
+// given the source it was added with, so it can take the item, value or
+// event that made the source ready.
+
+pub struct SelectArms<'a, R> {
+    select: Select<'a>,
+    arms: Vec<Box<dyn FnOnce() -> R + 'a>>,
+}
+
+impl<'a, R> SelectArms<'a, R> {
+    // -----------------------------------------------------------------------
+
+    pub fn new() -> Self {
+        SelectArms {
+            select: Select::new(),
+            arms: Vec::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // run 'arm' if 'channel' is the first source ready
+
+    pub fn on<T>(
+        mut self,
+        channel: &'a Channel<T>,
+        arm: impl FnOnce(&'a Channel<T>) -> R + 'a,
+    ) -> Self {
+        self.select.add(channel);
+        self.arms.push(Box::new(move || arm(channel)));
+        self
+    }
+
+    // -----------------------------------------------------------------------
+    // run 'arm' if 'latent' is the first source resolved
+
+    pub fn on_latent<T>(
+        mut self,
+        latent: &'a Latent<T>,
+        arm: impl FnOnce(&'a Latent<T>) -> R + 'a,
+    ) -> Self {
+        self.select.add_latent(latent);
+        self.arms.push(Box::new(move || arm(latent)));
+        self
+    }
+
+    // -----------------------------------------------------------------------
+    // run 'arm' if 'gate' is the first source open
+
+    pub fn on_gate(mut self, gate: &'a Gate, arm: impl FnOnce(&'a Gate) -> R + 'a) -> Self {
+        self.select.add_gate(gate);
+        self.arms.push(Box::new(move || arm(gate)));
+        self
+    }
+
+    // -----------------------------------------------------------------------
+    // run 'arm' if an event of 'listener' is the first to fire
+
+    pub fn on_listener<T>(
+        mut self,
+        listener: &'a EventListener<T>,
+        arm: impl FnOnce(&'a EventListener<T>) -> R + 'a,
+    ) -> Self {
+        self.select.add_listener(listener);
+        self.arms.push(Box::new(move || arm(listener)));
+        self
+    }
+
+    // -----------------------------------------------------------------------
+    // block until a source is ready & return what its arm returns. returns
+    // 'None' if no arms were added.
+
+    pub fn wait(mut self) -> Option<R> {
+        let index = self.select.wait()?;
+        Some(self.arms.swap_remove(index)())
+    }
+}
+
+impl<R> Default for SelectArms<'_, R> {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        SelectArms::new()
+    }
+}
+
+// lets a latent be added to a select alongside channels
+
+impl<T> ChannelWait for Latent<T> {
+    // -----------------------------------------------------------------------
+
+    fn add_event(&self, event: Event<usize>, listener_id: usize) {
+        self.register_waiter(listener_id, event);
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn remove_event(&self, listener_id: usize) {
+        self.deregister(listener_id);
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn is_ready(&self) -> bool {
+        Latent::is_ready(self)
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_select() {
+        let numbers = Channel::<i32>::new();
+        let numbers_producer = numbers.clone();
+        let words = Channel::<String>::new();
+        let producer = words.clone();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            producer.put("hello".to_string()).unwrap();
+        });
+
+        let mut select = Select::new();
+        assert_eq!(select.add(&numbers), 0);
+        assert_eq!(select.add(&words), 1);
+
+        assert_eq!(select.wait(), Some(1));
+        assert_eq!(words.get().unwrap(), "hello");
+        handle.join().unwrap();
+
+        numbers_producer.put(5).unwrap();
+        assert_eq!(select.wait(), Some(0));
+        assert!(Select::new().wait().is_none());
+    }
+
+    // -----------------------------------------------------------------------
+    // test a select over a channel, a latent & a gate
+
+    #[test]
+    fn validate_select_mixed() {
+        let channel = Channel::<i32>::new();
+        let _producer = channel.clone();
+        let (setter, latent) = Latent::<String>::pair();
+        let gate = Gate::new();
+
+        let mut select = Select::new();
+        assert_eq!(select.add(&channel), 0);
+        assert_eq!(select.add_latent(&latent), 1);
+        assert_eq!(select.add_gate(&gate), 2);
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(20));
+                gate.open();
+            });
+
+            assert_eq!(select.wait(), Some(2));
+        });
+
+        gate.close();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            setter.set("done".to_string());
+        });
+
+        assert_eq!(select.wait(), Some(1));
+        assert_eq!(latent.wait_ref(), "done");
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+    // a channel with no other handle left is ready, since 'get' won't block
+
+    #[test]
+    fn validate_select_disconnected() {
+        let channel = Channel::<i32>::new();
+        let producer = channel.clone();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            drop(producer);
+        });
+
+        let mut select = Select::new();
+        select.add(&channel);
+        assert_eq!(select.wait(), Some(0));
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+    // test a select across listeners of different value types
+
+    #[test]
+    fn validate_select_listeners() {
+        let mut control = EventListener::<&str>::new();
+        let mut data = EventListener::<usize>::new();
+        let _pending = control.create_event("stop");
+        let event = data.create_event(42);
+
+        let mut select = Select::new();
+        assert_eq!(select.add_listener(&control), 0);
+        assert_eq!(select.add_listener(&data), 1);
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            event.trigger();
+        });
+
+        assert_eq!(select.wait(), Some(1));
+        assert_eq!(data.wait_one(), Some(42));
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+    // test that only the arm of the ready source runs
+
+    #[test]
+    fn validate_select_arms() {
+        let numbers = Channel::<i32>::new();
+        let _numbers_producer = numbers.clone();
+        let words = Channel::<String>::new();
+        let producer = words.clone();
+        let (setter, latent) = Latent::<i32>::pair();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            producer.put("hello".to_string()).unwrap();
+        });
+
+        let result = SelectArms::new()
+            .on(&numbers, |numbers| numbers.get().unwrap().to_string())
+            .on(&words, |words| words.get().unwrap())
+            .on_latent(&latent, |latent| latent.wait_ref().to_string())
+            .wait();
+
+        assert_eq!(result.as_deref(), Some("hello"));
+        handle.join().unwrap();
+
+        setter.set(5);
+        let result = SelectArms::new()
+            .on(&numbers, |_| "numbers")
+            .on_latent(&latent, |_| "latent")
+            .wait();
+
+        assert_eq!(result, Some("latent"));
+        assert_eq!(SelectArms::<()>::new().wait(), None);
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+// ===========================================================================
+// ** Semaphore **
+// ===========================================================================
+
+// a counting semaphore that hands out up to a fixed number of permits, for
+// limiting how many threads use a resource at once. a permit is returned
+// when its 'Permit' guard is dropped.
+
+pub struct Semaphore {
+    permits: Mutex<usize>,
+    condvar: Condvar,
+}
+
+impl Semaphore {
+    // -----------------------------------------------------------------------
+
+    pub fn new(permits: usize) -> Self {
+        Semaphore {
+            permits: Mutex::new(permits),
+            condvar: Condvar::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn arc(permits: usize) -> Arc<Self> {
+        Arc::new(Semaphore::new(permits))
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the number of permits not currently held
+
+    pub fn available(&self) -> usize {
+        *self.permits.lock().unwrap()
+    }
+
+    // -----------------------------------------------------------------------
+    // block until a permit is free & take it
+
+    pub fn acquire(&self) -> Permit<'_> {
+        let permits = self.permits.lock().unwrap();
+        let mut permits = self
+            .condvar
+            .wait_while(permits, |permits| *permits == 0)
+            .unwrap();
+
+        *permits -= 1;
+        Permit { semaphore: self }
+    }
+
+    // -----------------------------------------------------------------------
+    // take a permit if one is free, without blocking
+
+    pub fn try_acquire(&self) -> Option<Permit<'_>> {
+        let mut permits = self.permits.lock().unwrap();
+
+        if *permits == 0 {
+            return None;
+        }
+
+        *permits -= 1;
+        Some(Permit { semaphore: self })
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'acquire' but returns 'None' if no permit frees up within 'timeout'
+
+    pub fn acquire_timeout(&self, timeout: Duration) -> Option<Permit<'_>> {
+        let permits = self.permits.lock().unwrap();
+        let (mut permits, _) = self
+            .condvar
+            .wait_timeout_while(permits, timeout, |permits| *permits == 0)
+            .unwrap();
+
+        if *permits == 0 {
+            return None;
+        }
+
+        *permits -= 1;
+        Some(Permit { semaphore: self })
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn release(&self) {
+        *self.permits.lock().unwrap() += 1;
+        self.condvar.notify_one();
+    }
+}
+
+// ===========================================================================
+// ** Permit **
+// ===========================================================================
+
+pub struct Permit<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl Drop for Permit<'_> {
+    // -----------------------------------------------------------------------
+
+    fn drop(&mut self) {
+        self.semaphore.release();
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::{AtomicInteger, ThreadPool};
+    use std::thread;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_semaphore() {
+        let pool = ThreadPool::new(6);
+        let connections = Semaphore::arc(2);
+        let active = Arc::new(AtomicInteger::new(0));
+        let peak = Arc::new(AtomicInteger::new(0));
+
+        let done: Vec<_> = (0..6)
+            .map(|_| {
+                let connections = connections.clone();
+                let active = active.clone();
+                let peak = peak.clone();
+
+                pool.put(move || {
+                    let _permit = connections.acquire();
+                    let count = active.increment() + 1;
+
+                    if count > peak.get() {
+                        peak.set(count);
+                    }
+
+                    thread::sleep(Duration::from_millis(10));
+                    active.decrement();
+                })
+            })
+            .collect();
+
+        done.into_iter().for_each(|latent| latent.wait());
+        assert!(peak.get() <= 2);
+        assert_eq!(connections.available(), 2);
+
+        let first = connections.try_acquire().unwrap();
+        let _second = connections.acquire();
+        assert!(connections.try_acquire().is_none());
+        assert!(
+            connections
+                .acquire_timeout(Duration::from_millis(20))
+                .is_none()
+        );
+
+        drop(first);
+        assert!(connections.try_acquire().is_some());
+    }
+}
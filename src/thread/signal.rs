@@ -1,46 +1,186 @@
+use crate::thread::Event;
+use crate::thread::channel::ChannelWait;
+use std::collections::HashMap;
 use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 
 // ===========================================================================
 // ** Signal **
 // ===========================================================================
 
+// each 'signal_one' leaves a permit that 'wait' consumes, so a signal sent
+// before the wait begins isn't lost. 'signal_all' bumps 'broadcasts'
+// instead, & a waiter passes once the count has changed since it began
+// waiting, so a broadcast releases exactly the threads already waiting & a
+// later arrival can't take their place. 'count' is the number of signals
+// sent, which 'wait' returns. a level-triggered signal is 'raised'
+// instead, like a manual-reset event, & every wait returns straight away
+// until it is 'reset'.
+
+struct SignalState {
+    permits: u32,
+    waiting: u32,
+    broadcasts: u64,
+    count: u32,
+    level_triggered: bool,
+    raised: bool,
+}
+
+impl SignalState {
+    // -----------------------------------------------------------------------
+
+    // returns 'true' if a waiter that began at broadcast 'epoch' may pass
+
+    fn is_ready(&self, epoch: u64) -> bool {
+        self.raised || self.broadcasts != epoch || self.permits > 0
+    }
+
+    // -----------------------------------------------------------------------
+    // let a waiter through, consuming a permit unless the signal is raised or
+    // a broadcast has released it
+
+    fn pass(&mut self, epoch: u64) -> u32 {
+        if !self.raised && self.broadcasts == epoch {
+            self.permits -= 1;
+        }
+
+        self.count
+    }
+}
+
 pub struct Signal {
     cvar: Condvar,
-    mutex: Mutex<u32>,
+    mutex: Mutex<SignalState>,
 }
 
 impl Signal {
     // -----------------------------------------------------------------------
 
     pub fn new() -> Self {
+        Signal::create(false)
+    }
+
+    // -----------------------------------------------------------------------
+    // create a signal that stays raised once signalled, releasing every wait
+    // until 'reset' is called
+
+    pub fn level_triggered() -> Self {
+        Signal::create(true)
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn create(level_triggered: bool) -> Self {
         Signal {
             cvar: Condvar::new(),
-            mutex: Mutex::new(0),
+            mutex: Mutex::new(SignalState {
+                permits: 0,
+                waiting: 0,
+                broadcasts: 0,
+                count: 0,
+                level_triggered,
+                raised: false,
+            }),
         }
     }
 
     // -----------------------------------------------------------------------
 
     pub fn signal_all(&self) {
-        let mut value = self.mutex.lock().unwrap();
-        *value += 1;
-        self.cvar.notify_all();
+        let mut state = self.mutex.lock().unwrap();
+        state.count += 1;
+
+        if state.level_triggered {
+            state.raised = true;
+        } else {
+            state.broadcasts += 1;
+        }
+
+        if state.waiting > 0 {
+            self.cvar.notify_all();
+        }
     }
 
     // -----------------------------------------------------------------------
+    // a level-triggered signal is raised for every waiter, same as
+    // 'signal_all'
 
     pub fn signal_one(&self) {
-        let mut value = self.mutex.lock().unwrap();
-        *value += 1;
+        let mut state = self.mutex.lock().unwrap();
+
+        if state.level_triggered {
+            drop(state);
+            self.signal_all();
+            return;
+        }
+
+        state.count += 1;
+        state.permits += 1;
         self.cvar.notify_one();
     }
 
     // -----------------------------------------------------------------------
+    // lower a raised signal & drop any permits not yet consumed
+
+    pub fn reset(&self) {
+        let mut state = self.mutex.lock().unwrap();
+        state.raised = false;
+        state.permits = 0;
+    }
+
+    // -----------------------------------------------------------------------
+    // returns 'true' while a level-triggered signal is raised
+
+    pub fn is_raised(&self) -> bool {
+        self.mutex.lock().unwrap().raised
+    }
+
+    // -----------------------------------------------------------------------
+    // block until a permit is available & consume it. returns straight away
+    // if a signal was sent before the wait began.
 
     pub fn wait(&self) -> u32 {
-        let guard = self.mutex.lock().unwrap();
-        let value = self.cvar.wait(guard).unwrap();
-        *value
+        let mut state = self.mutex.lock().unwrap();
+        let epoch = state.broadcasts;
+        state.waiting += 1;
+
+        let mut state = self
+            .cvar
+            .wait_while(state, |state| !state.is_ready(epoch))
+            .unwrap();
+
+        state.waiting -= 1;
+        state.pass(epoch)
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait' but returns 'None' if no signal arrives within 'timeout'
+
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<u32> {
+        let mut state = self.mutex.lock().unwrap();
+        let epoch = state.broadcasts;
+        state.waiting += 1;
+
+        let (mut state, _) = self
+            .cvar
+            .wait_timeout_while(state, timeout, |state| !state.is_ready(epoch))
+            .unwrap();
+
+        state.waiting -= 1;
+
+        if !state.is_ready(epoch) {
+            return None;
+        }
+
+        Some(state.pass(epoch))
+    }
+}
+
+impl Default for Signal {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        Signal::new()
     }
 }
 
@@ -48,9 +188,39 @@ impl Signal {
 // ** Gate **
 // ===========================================================================
 
+// 'generation' counts the times the gate has opened, so a waiter that checked
+// it before a quick open & close still sees that it opened. 'passes' are let
+// through a closed gate one waiter each, like a turnstile.
+
+struct GateState {
+    open: bool,
+    generation: u64,
+    passes: usize,
+}
+
+impl GateState {
+    // -----------------------------------------------------------------------
+    // returns 'true' if a waiter that read 'generation' may go through,
+    // using up a pass if the gate is closed
+
+    fn try_pass(&mut self, generation: u64) -> bool {
+        if self.open || self.generation > generation {
+            return true;
+        }
+
+        if self.passes > 0 {
+            self.passes -= 1;
+            return true;
+        }
+
+        false
+    }
+}
+
 pub struct Gate {
     condvar: Condvar,
-    mutex: Mutex<bool>,
+    state: Mutex<GateState>,
+    events: Mutex<HashMap<usize, Event<usize>>>,
 }
 
 impl Gate {
@@ -59,7 +229,12 @@ impl Gate {
     pub fn new() -> Self {
         Gate {
             condvar: Condvar::new(),
-            mutex: Mutex::new(false),
+            state: Mutex::new(GateState {
+                open: false,
+                generation: 0,
+                passes: 0,
+            }),
+            events: Mutex::new(HashMap::new()),
         }
     }
 
@@ -72,26 +247,145 @@ impl Gate {
     // -----------------------------------------------------------------------
 
     pub fn open(&self) {
-        let mut open = self.mutex.lock().unwrap();
-        *open = true;
-        self.condvar.notify_all();
+        let mut state = self.state.lock().unwrap();
+
+        if !state.open {
+            state.open = true;
+            state.generation += 1;
+        }
+
+        state.passes = 0;
+        self.wake();
+    }
+
+    // -----------------------------------------------------------------------
+    // let 'count' waiters through the closed gate, one at a time, after which
+    // it stays closed. the passes add up across calls until used.
+
+    pub fn open_n(&self, count: usize) {
+        let mut state = self.state.lock().unwrap();
+
+        if state.open || count == 0 {
+            return;
+        }
+
+        state.passes += count;
+        self.wake();
     }
 
     // -----------------------------------------------------------------------
+    // close the gate & take back any passes left from 'open_n'
 
     pub fn close(&self) {
-        let mut open = self.mutex.lock().unwrap();
-        *open = false;
+        let mut state = self.state.lock().unwrap();
+        state.open = false;
+        state.passes = 0;
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn is_open(&self) -> bool {
+        self.state.lock().unwrap().open
+    }
+
+    // -----------------------------------------------------------------------
+    // wake the waiters & selects. called with 'state' locked.
+
+    fn wake(&self) {
+        self.condvar.notify_all();
+
+        for (_, event) in self.events.lock().unwrap().drain() {
+            event.trigger();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the number of times the gate has opened, to pass to
+    // 'wait_for_open_since'
+
+    pub fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
     }
 
     // -----------------------------------------------------------------------
+    // block until the gate is open. an open that is closed again before this
+    // thread wakes still releases it.
 
     pub fn wait(&self) {
-        let mut open = self.mutex.lock().unwrap();
+        self.wait_for_open_since(self.generation());
+    }
+
+    // -----------------------------------------------------------------------
+    // block until the gate is open or has opened since 'generation' was read,
+    // even if it has closed again. returns the current generation.
+
+    pub fn wait_for_open_since(&self, generation: u64) -> u64 {
+        let mut state = self.state.lock().unwrap();
+
+        while !state.try_pass(generation) {
+            state = self.condvar.wait(state).unwrap();
+        }
+
+        state.generation
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait' but gives up after 'timeout'. returns 'true' if this thread
+    // was let through, 'false' if the gate stayed closed.
+
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock().unwrap();
+        let generation = state.generation;
+
+        while !state.try_pass(generation) {
+            let now = Instant::now();
 
-        while !*open {
-            open = self.condvar.wait(open).unwrap();
+            if now >= deadline {
+                return false;
+            }
+
+            state = self.condvar.wait_timeout(state, deadline - now).unwrap().0;
         }
+
+        true
+    }
+}
+
+impl Default for Gate {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        Gate::new()
+    }
+}
+
+// lets a gate be added to a 'Select', which treats an open gate as ready
+
+impl ChannelWait for Gate {
+    // -----------------------------------------------------------------------
+
+    fn add_event(&self, event: Event<usize>, listener_id: usize) {
+        let state = self.state.lock().unwrap();
+
+        if state.open || state.passes > 0 {
+            event.trigger();
+        } else {
+            self.events.lock().unwrap().insert(listener_id, event);
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn remove_event(&self, listener_id: usize) {
+        self.events.lock().unwrap().remove(&listener_id);
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn is_ready(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.open || state.passes > 0
     }
 }
 
@@ -102,9 +396,9 @@ impl Gate {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::thread::AtomicInteger;
     use std::sync::Arc;
     use std::thread;
-    use std::time::Duration;
 
     // -----------------------------------------------------------------------
     // ensure the signal is working
@@ -122,6 +416,87 @@ mod tests {
         handle.join().unwrap();
     }
 
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_signal_wait_timeout() {
+        let signal = Arc::new(Signal::new());
+        assert_eq!(signal.wait_timeout(Duration::from_millis(20)), None);
+
+        let signal_clone = signal.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            signal_clone.signal_one();
+        });
+
+        assert_eq!(signal.wait_timeout(Duration::from_secs(5)), Some(1));
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+    // a signal sent before the wait begins isn't lost
+
+    #[test]
+    fn validate_signal_permits() {
+        let signal = Signal::new();
+        signal.signal_one();
+        signal.signal_one();
+        assert_eq!(signal.wait(), 2);
+        assert_eq!(signal.wait(), 2);
+        assert_eq!(signal.wait_timeout(Duration::from_millis(20)), None);
+
+        // 'signal_all' with nobody waiting leaves no permit behind
+
+        signal.signal_all();
+        assert_eq!(signal.wait_timeout(Duration::from_millis(20)), None);
+
+        // 'signal_all' releases every waiting thread
+
+        thread::scope(|scope| {
+            let waits: Vec<_> = (0..3).map(|_| scope.spawn(|| signal.wait())).collect();
+
+            while signal.mutex.lock().unwrap().waiting < 3 {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            signal.signal_all();
+
+            // a wait that begins after the broadcast can't take a waiter's
+            // place
+
+            assert_eq!(signal.wait_timeout(Duration::from_millis(20)), None);
+
+            for wait in waits {
+                assert_eq!(wait.join().unwrap(), 4);
+            }
+        });
+    }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_signal_level_triggered() {
+        let signal = Signal::level_triggered();
+        let timeout = Duration::from_millis(20);
+        assert_eq!(signal.wait_timeout(timeout), None);
+
+        signal.signal_one();
+        assert!(signal.is_raised());
+        assert_eq!(signal.wait(), 1);
+        assert_eq!(signal.wait(), 1);
+
+        signal.reset();
+        assert!(!signal.is_raised());
+        assert_eq!(signal.wait_timeout(timeout), None);
+
+        // reset also drops the permits of an edge-triggered signal
+
+        let signal = Signal::new();
+        signal.signal_one();
+        signal.reset();
+        assert_eq!(signal.wait_timeout(timeout), None);
+    }
+
     // -----------------------------------------------------------------------
     // ensure the gate is working
 
@@ -131,4 +506,68 @@ mod tests {
         gate.open();
         gate.wait(); // shouldn't wait if gate is already open
     }
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_gate_wait_timeout() {
+        let gate = Arc::new(Gate::new());
+        assert!(!gate.wait_timeout(Duration::from_millis(20)));
+
+        let gate_clone = gate.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            gate_clone.open();
+        });
+
+        assert!(gate.wait_timeout(Duration::from_secs(5)));
+        handle.join().unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+    // a quick open & close between the check & the wait isn't missed
+
+    #[test]
+    fn validate_gate_generation() {
+        let gate = Gate::new();
+        let seen = gate.generation();
+
+        gate.open();
+        gate.close();
+        assert_eq!(gate.wait_for_open_since(seen), 1);
+
+        gate.open();
+        assert_eq!(gate.wait_for_open_since(2), 2);
+        assert_eq!(gate.generation(), 2);
+    }
+
+    // -----------------------------------------------------------------------
+    // 'open_n' lets exactly that many waiters through
+
+    #[test]
+    fn validate_gate_open_n() {
+        let gate = Gate::new();
+        let passed = AtomicInteger::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..5 {
+                scope.spawn(|| {
+                    if gate.wait_timeout(Duration::from_millis(200)) {
+                        passed.increment();
+                    }
+                });
+            }
+
+            thread::sleep(Duration::from_millis(20));
+            gate.open_n(2);
+            gate.open_n(1);
+        });
+
+        assert_eq!(passed.get(), 3);
+        assert!(!gate.wait_timeout(Duration::from_millis(20)));
+
+        gate.open_n(1);
+        gate.close();
+        assert!(!gate.wait_timeout(Duration::from_millis(20)));
+    }
 }
@@ -0,0 +1,102 @@
+use std::hint;
+use std::sync::{Mutex, MutexGuard, TryLockError};
+
+// ===========================================================================
+// ** SpinLock **
+// ===========================================================================
+
+// a lock for very short critical sections. 'lock' spins on 'try_lock' for a
+// while before falling back to the mutex's blocking path, which parks the
+// thread, so a lock that is released quickly is taken without a syscall.
+
+const DEFAULT_SPINS: u32 = 100;
+
+pub struct SpinLock<T> {
+    mutex: Mutex<T>,
+    spins: u32,
+}
+
+impl<T> SpinLock<T> {
+    // -----------------------------------------------------------------------
+
+    pub fn new(value: T) -> Self {
+        SpinLock::with_spins(value, DEFAULT_SPINS)
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'new' but spins 'spins' times before parking
+
+    pub fn with_spins(value: T, spins: u32) -> Self {
+        SpinLock {
+            mutex: Mutex::new(value),
+            spins,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        for _ in 0..self.spins {
+            match self.mutex.try_lock() {
+                Ok(guard) => return guard,
+                Err(TryLockError::WouldBlock) => hint::spin_loop(),
+                Err(TryLockError::Poisoned(_)) => break,
+            }
+        }
+
+        self.mutex.lock().unwrap()
+    }
+
+    // -----------------------------------------------------------------------
+    // take the lock if it is free, without spinning or blocking
+
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.mutex.try_lock().ok()
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn into_inner(self) -> T {
+        self.mutex.into_inner().unwrap()
+    }
+}
+
+impl<T: Default> Default for SpinLock<T> {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        SpinLock::new(T::default())
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_spin_lock() {
+        let counter = SpinLock::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        *counter.lock() += 1;
+                    }
+                });
+            }
+        });
+
+        let guard = counter.lock();
+        assert!(counter.try_lock().is_none());
+        drop(guard);
+        assert_eq!(counter.into_inner(), 4000);
+    }
+}
@@ -0,0 +1,317 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+// the fewest callbacks a token keeps before it prunes the dead ones
+
+const MIN_PRUNE: usize = 16;
+
+// ===========================================================================
+// ** StopSource **
+// ===========================================================================
+
+// requests a stop that every 'StopToken' taken from it can check or wait
+// for. channels, latents & pools can be tied to a token with their 'stop_on'
+// methods, so a single 'stop' ends channels, resolves latents & drops queued
+// tasks across the crate. a callback registered with 'on_stop_weak' is
+// pruned once what it stops has been dropped, so short-lived channels &
+// latents don't pile up on a long-lived token. 'prune_at' is the callback
+// count that triggers the next pruning, doubling each time so registering
+// stays cheap.
+
+struct StopCallback {
+    is_alive: Box<dyn Fn() -> bool + Send>,
+    run: Box<dyn FnOnce() + Send>,
+}
+
+struct StopState {
+    stopped: bool,
+    callbacks: Vec<StopCallback>,
+    prune_at: usize,
+}
+
+struct StopData {
+    state: Mutex<StopState>,
+    condvar: Condvar,
+}
+
+#[derive(Clone)]
+pub struct StopSource {
+    shared: Arc<StopData>,
+}
+
+impl StopSource {
+    // -----------------------------------------------------------------------
+
+    pub fn new() -> Self {
+        StopSource {
+            shared: Arc::new(StopData {
+                state: Mutex::new(StopState {
+                    stopped: false,
+                    callbacks: Vec::new(),
+                    prune_at: MIN_PRUNE,
+                }),
+                condvar: Condvar::new(),
+            }),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn token(&self) -> StopToken {
+        StopToken {
+            shared: self.shared.clone(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // request the stop, waking the waiters & running the callbacks on this
+    // thread. returns 'false' if the stop was already requested.
+
+    pub fn stop(&self) -> bool {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if state.stopped {
+            return false;
+        }
+
+        state.stopped = true;
+        let callbacks = std::mem::take(&mut state.callbacks);
+        self.shared.condvar.notify_all();
+        drop(state);
+
+        for callback in callbacks {
+            (callback.run)();
+        }
+
+        true
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn is_stopped(&self) -> bool {
+        self.shared.state.lock().unwrap().stopped
+    }
+}
+
+impl Default for StopSource {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        StopSource::new()
+    }
+}
+
+// ===========================================================================
+// ** StopToken **
+// ===========================================================================
+
+#[derive(Clone)]
+pub struct StopToken {
+    shared: Arc<StopData>,
+}
+
+impl StopToken {
+    // -----------------------------------------------------------------------
+
+    pub fn is_stopped(&self) -> bool {
+        self.shared.state.lock().unwrap().stopped
+    }
+
+    // -----------------------------------------------------------------------
+    // block until the stop is requested
+
+    pub fn wait(&self) {
+        let state = self.shared.state.lock().unwrap();
+        let _state = self
+            .shared
+            .condvar
+            .wait_while(state, |state| !state.stopped)
+            .unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait' but returns 'false' if the stop isn't requested within
+    // 'timeout'
+
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let state = self.shared.state.lock().unwrap();
+        let (state, _) = self
+            .shared
+            .condvar
+            .wait_timeout_while(state, timeout, |state| !state.stopped)
+            .unwrap();
+
+        state.stopped
+    }
+
+    // -----------------------------------------------------------------------
+    // run 'callback' when the stop is requested, or straight away if it
+    // already has been. the callback is kept until then, so it should only
+    // hold weak references to what it stops.
+
+    pub fn on_stop(&self, callback: impl FnOnce() + Send + 'static) {
+        self.register(StopCallback {
+            is_alive: Box::new(|| true),
+            run: Box::new(callback),
+        });
+    }
+
+    // -----------------------------------------------------------------------
+    // run 'callback' with 'target' when the stop is requested, if 'target' is
+    // still alive. only a weak reference is kept, & the callback is dropped
+    // once 'target' is.
+
+    pub(crate) fn on_stop_weak<D: Send + Sync + 'static>(
+        &self,
+        target: &Arc<D>,
+        callback: impl FnOnce(Arc<D>) + Send + 'static,
+    ) {
+        let weak = Arc::downgrade(target);
+        let alive = weak.clone();
+
+        self.register(StopCallback {
+            is_alive: Box::new(move || alive.strong_count() > 0),
+            run: Box::new(move || {
+                if let Some(target) = weak.upgrade() {
+                    callback(target);
+                }
+            }),
+        });
+    }
+
+    // -----------------------------------------------------------------------
+    // keep 'callback' until the stop, pruning the dead ones once the list has
+    // doubled since the last pruning, or run it now if the stop was requested
+
+    fn register(&self, callback: StopCallback) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if state.stopped {
+            drop(state);
+            (callback.run)();
+            return;
+        }
+
+        if state.callbacks.len() >= state.prune_at {
+            state.callbacks.retain(|callback| (callback.is_alive)());
+            state.prune_at = (state.callbacks.len() * 2).max(MIN_PRUNE);
+        }
+
+        state.callbacks.push(callback);
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::{
+        AtomicInteger, Channel, Latent, LatentError, RecvError, Signal, ThreadPool,
+    };
+    use std::thread;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_stop_token() {
+        let source = StopSource::new();
+        let token = source.token();
+        assert!(!token.wait_timeout(Duration::from_millis(20)));
+
+        let waiter = token.clone();
+        let handle = thread::spawn(move || waiter.wait());
+
+        let stopped = Arc::new(AtomicInteger::new(0));
+        let counter = stopped.clone();
+        token.on_stop(move || {
+            counter.increment();
+        });
+
+        assert!(source.stop());
+        assert!(!source.stop());
+        handle.join().unwrap();
+        assert!(token.is_stopped());
+        assert_eq!(stopped.get(), 1);
+
+        // a callback added after the stop runs straight away
+
+        let counter = stopped.clone();
+        token.on_stop(move || {
+            counter.increment();
+        });
+
+        assert_eq!(stopped.get(), 2);
+    }
+
+    // -----------------------------------------------------------------------
+    // test that the callbacks of dropped latents & channels are pruned
+
+    #[test]
+    fn validate_stop_prune() {
+        let source = StopSource::new();
+        let token = source.token();
+
+        for _ in 0..1000 {
+            let latent = Latent::<i32>::new();
+            latent.stop_on(&token);
+            Channel::<i32>::new().stop_on(&token);
+        }
+
+        let kept = token.shared.state.lock().unwrap().callbacks.len();
+        assert!(kept <= 2 * MIN_PRUNE);
+
+        let latent = Latent::<i32>::new();
+        latent.stop_on(&token);
+        source.stop();
+        assert!(latent.is_stopped());
+    }
+
+    // -----------------------------------------------------------------------
+    // test a single stop across a pool, a channel & a latent
+
+    #[test]
+    fn validate_stop_source() {
+        let source = StopSource::new();
+        let token = source.token();
+
+        let pool = ThreadPool::new(1);
+        pool.stop_on(&token);
+
+        let blocker = Arc::new(Signal::new());
+        let started = Arc::new(Signal::new());
+        let running = {
+            let blocker = blocker.clone();
+            let started = started.clone();
+
+            pool.put(move || {
+                started.signal_one();
+                blocker.wait();
+            })
+        };
+
+        let queued = pool.put(|| 1);
+        started.wait();
+
+        let channel = Channel::<i32>::new();
+        let _producer = channel.clone();
+        channel.stop_on(&token);
+
+        let (_setter, latent) = Latent::<i32>::pair();
+        latent.stop_on(&token);
+
+        let consumer = channel.clone();
+        let handle = thread::spawn(move || consumer.get());
+
+        source.stop();
+        assert_eq!(handle.join().unwrap(), Err(RecvError::Closed));
+        assert_eq!(latent.try_wait(), Err(LatentError::Stopped));
+        assert!(latent.is_stopped());
+        assert_eq!(queued.try_wait(), Err(LatentError::Abandoned));
+
+        blocker.signal_one();
+        running.wait();
+    }
+}
@@ -0,0 +1,133 @@
+use crate::thread::{Channel, OverflowPolicy, RecvError, StopSource};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ===========================================================================
+// ** Ticker **
+// ===========================================================================
+
+// puts the current time into a channel once every period, from a thread of
+// its own. the ticks are scheduled from the start time so they don't drift,
+// & the channel holds a single tick, so a slow consumer sees the latest one
+// rather than a backlog. stopping the ticker closes the channel.
+
+pub struct Ticker {
+    ticks: Channel<Instant>,
+    stop: StopSource,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl Ticker {
+    // -----------------------------------------------------------------------
+
+    pub fn new(period: Duration) -> Self {
+        let ticks = Channel::bounded_with(1, OverflowPolicy::DropOldest);
+        let stop = StopSource::new();
+        let token = stop.token();
+        ticks.stop_on(&token);
+
+        let producer = ticks.clone();
+        let handle = thread::spawn(move || {
+            let mut next = Instant::now() + period;
+
+            loop {
+                let remaining = next.saturating_duration_since(Instant::now());
+
+                if token.wait_timeout(remaining) || producer.put(Instant::now()).is_err() {
+                    break;
+                }
+
+                next += period;
+            }
+        });
+
+        Ticker {
+            ticks,
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // block until the next tick & return the time it was sent. returns
+    // 'None' once the ticker is stopped.
+
+    pub fn tick(&self) -> Option<Instant> {
+        self.ticks.get().ok()
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'tick' but gives up after 'timeout'
+
+    pub fn tick_timeout(&self, timeout: Duration) -> Result<Instant, RecvError> {
+        self.ticks.get_timeout(timeout)
+    }
+
+    // -----------------------------------------------------------------------
+    // returns a handle to the channel the ticks are put into, so they can be
+    // waited on in a 'Select'
+
+    pub fn channel(&self) -> Channel<Instant> {
+        self.ticks.clone()
+    }
+
+    // -----------------------------------------------------------------------
+    // stop the ticks & close the channel. ticks already queued can still be
+    // taken.
+
+    pub fn stop(&self) {
+        self.stop.stop();
+    }
+
+    // -----------------------------------------------------------------------
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.is_stopped()
+    }
+}
+
+impl Drop for Ticker {
+    // -----------------------------------------------------------------------
+
+    fn drop(&mut self) {
+        self.stop();
+
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_ticker() {
+        let period = Duration::from_millis(10);
+        let ticker = Ticker::new(period);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            ticker.tick().unwrap();
+        }
+
+        assert!(start.elapsed() >= period * 2);
+
+        let ticks = ticker.channel();
+        ticker.stop();
+        assert!(ticker.is_stopped());
+
+        // at most one tick was queued before the channel closed
+
+        let _ = ticks.get();
+        assert_eq!(ticks.get(), Err(RecvError::Closed));
+        assert!(ticker.tick().is_none());
+    }
+}
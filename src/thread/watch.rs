@@ -0,0 +1,170 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+// ===========================================================================
+// ** Watch **
+// ===========================================================================
+
+// a value that can be replaced any number of times. every update bumps the
+// version, and waiters block until the version is newer than the one they
+// last saw, so each waiter sees the latest value without missing a change.
+
+struct WatchState<T> {
+    value: T,
+    version: u64,
+}
+
+struct WatchData<T> {
+    state: Mutex<WatchState<T>>,
+    condvar: Condvar,
+}
+
+pub struct Watch<T> {
+    shared: Arc<WatchData<T>>,
+}
+
+impl<T> Watch<T> {
+    // -----------------------------------------------------------------------
+    // create a watch holding 'value' at version 0
+
+    pub fn new(value: T) -> Self {
+        Watch {
+            shared: Arc::new(WatchData {
+                state: Mutex::new(WatchState { value, version: 0 }),
+                condvar: Condvar::new(),
+            }),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // replace the value & wake the waiters
+
+    pub fn set(&self, value: T) {
+        self.update(|current| *current = value);
+    }
+
+    // -----------------------------------------------------------------------
+    // modify the value in place & wake the waiters
+
+    pub fn update(&self, func: impl FnOnce(&mut T)) {
+        let mut state = self.shared.state.lock().unwrap();
+        func(&mut state.value);
+        state.version += 1;
+        self.shared.condvar.notify_all();
+    }
+
+    // -----------------------------------------------------------------------
+    // returns the number of updates made so far
+
+    pub fn version(&self) -> u64 {
+        self.shared.state.lock().unwrap().version
+    }
+}
+
+impl<T: Clone> Watch<T> {
+    // -----------------------------------------------------------------------
+    // returns the current value & its version
+
+    pub fn get(&self) -> (T, u64) {
+        let state = self.shared.state.lock().unwrap();
+        (state.value.clone(), state.version)
+    }
+
+    // -----------------------------------------------------------------------
+    // block until the version is newer than 'seen' & return the value with
+    // its version, which is passed as 'seen' to the next call
+
+    pub fn wait_newer(&self, seen: u64) -> (T, u64) {
+        self.wait_newer_until(seen, None).unwrap()
+    }
+
+    // -----------------------------------------------------------------------
+    // like 'wait_newer' but returns 'None' if no update arrives in 'timeout'
+
+    pub fn wait_newer_timeout(&self, seen: u64, timeout: Duration) -> Option<(T, u64)> {
+        self.wait_newer_until(seen, Some(Instant::now() + timeout))
+    }
+
+    // -----------------------------------------------------------------------
+
+    fn wait_newer_until(&self, seen: u64, deadline: Option<Instant>) -> Option<(T, u64)> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        while state.version <= seen {
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+
+                    if now >= deadline {
+                        return None;
+                    }
+
+                    self.shared
+                        .condvar
+                        .wait_timeout(state, deadline - now)
+                        .unwrap()
+                        .0
+                }
+                None => self.shared.condvar.wait(state).unwrap(),
+            };
+        }
+
+        Some((state.value.clone(), state.version))
+    }
+}
+
+impl<T> Clone for Watch<T> {
+    // -----------------------------------------------------------------------
+
+    fn clone(&self) -> Self {
+        Watch {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T: Default> Default for Watch<T> {
+    // -----------------------------------------------------------------------
+
+    fn default() -> Self {
+        Watch::new(T::default())
+    }
+}
+
+// ===========================================================================
+// ** TESTS **
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_watch() {
+        let config = Watch::new("v1".to_string());
+        let (value, seen) = config.get();
+        assert_eq!((value.as_str(), seen), ("v1", 0));
+
+        let publisher = config.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            publisher.set("v2".to_string());
+            publisher.update(|value| value.push('!'));
+        });
+
+        let (value, seen) = config.wait_newer(seen);
+        assert!(value == "v2" || value == "v2!");
+        handle.join().unwrap();
+
+        if seen == 1 {
+            assert_eq!(config.wait_newer(seen), ("v2!".to_string(), 2));
+        }
+
+        let timeout = Duration::from_millis(20);
+        assert!(config.wait_newer_timeout(2, timeout).is_none());
+        assert_eq!(config.version(), 2);
+    }
+}